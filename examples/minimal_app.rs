@@ -1,4 +1,3 @@
-use oml_game::math::Matrix44;
 use oml_game::math::Vector2;
 use oml_game::renderer::Color;
 use oml_game::renderer::Effect;
@@ -9,35 +8,41 @@ use oml_game::system::System;
 use oml_game::window::Window;
 use oml_game::window::WindowUpdateContext;
 use oml_game::App;
+use oml_game_egui::EguiEffect;
+use oml_game_egui::EguiLayerMap;
+use oml_game_egui::EguiOverlay;
+use oml_game_egui::EguiTrigger;
+use oml_game_egui::EguiTriggerCondition;
 use oml_game_egui::EguiWrapper;
+use oml_game_egui::EguiWrapperConfig;
 
 enum EffectId {
-	Default         = 0,
-	Textured        = 1,
-	ColoredTextured = 2,
-	Colored         = 3,
+	Default  = 0,
+	Textured = 1,
+	Egui     = 2,
+	Colored  = 3,
 }
 
 enum LayerId {
-	Egui  = 1,
-	Debug = 2,
+	Egui        = 1,
+	Debug       = 2,
+	EguiTooltip = 3,
 }
 
 #[derive(Debug, Default)]
 pub struct MinimalApp {
-	is_done:           bool,
-	total_time:        f64,
-	size:              Vector2,
-	viewport_size:     Vector2,
-	scaling:           f32,
-	renderer:          Option<Renderer>,
-	system:            System,
-	cursor_pos:        Vector2,
-	egui_wrapper:      EguiWrapper,
-	font_size:         u16,
-	use_blend_factors: bool,
-	cull_face:         bool,
-	frame_count:       usize,
+	is_done:       bool,
+	total_time:    f64,
+	size:          Vector2,
+	viewport_size: Vector2,
+	scaling:       f32,
+	renderer:      Option<Renderer>,
+	system:        System,
+	cursor_pos:    Vector2,
+	egui_wrapper:  EguiWrapper,
+	font_size:     u16,
+	cull_face:     bool,
+	frame_count:   usize,
 
 	telemetry: oml_game_egui::EguiTelemetryWidget,
 }
@@ -77,6 +82,8 @@ impl App for MinimalApp {
 
 		let mut lfs = FilesystemLayered::new();
 		self.add_filesystem_disk(&mut lfs, "./examples/data", false); // :TODO: fix path, relative to bin?
+		std::fs::create_dir_all("./examples/save")?;
+		self.add_filesystem_disk(&mut lfs, "./examples/save", true);
 		self.system.set_default_filesystem(Box::new(lfs));
 
 		let mut renderer = Renderer::new();
@@ -98,21 +105,7 @@ impl App for MinimalApp {
 			"textured_fs.glsl",
 		));
 
-		renderer.register_effect(
-			Effect::create(
-				&mut self.system,
-				EffectId::ColoredTextured as u16,
-				"ColoredTextured",
-				"coloredtextured_vs.glsl",
-				"coloredtextured_fs.glsl",
-			)
-			.with_cull_face(false), //.with_blend_func( oml_game::renderer::BlendFactor::One, oml_game::renderer::BlendFactor::OneMinusSrcAlpha )
-		);
-		/*
-		renderer.find_effect_mut_and_then( "ColoredTextured", |e| {
-			e.set_blend_func( oml_game::renderer::BlendFactor::One, oml_game::renderer::BlendFactor::OneMinusSrcAlpha );
-		});
-		*/
+		EguiEffect::register(&mut self.system, &mut renderer, EffectId::Egui as u16);
 		renderer.register_effect(Effect::create(
 			&mut self.system,
 			EffectId::Colored as u16,
@@ -126,18 +119,57 @@ impl App for MinimalApp {
 		let scale_factor = window.scale_factor() as f32;
 		tracing::debug!("scale_factor {}", scale_factor);
 		self.scaling = scale_factor;
-		self.egui_wrapper.setup(scale_factor);
-		self.egui_wrapper
-			.set_effect_id(EffectId::ColoredTextured as u16);
-		self.egui_wrapper.set_layer_id(LayerId::Egui as u8);
+		if let Some(renderer) = &mut self.renderer {
+			let config = EguiWrapperConfig::default()
+				.with_pixels_per_point(scale_factor)
+				.with_layer_map(
+					// the cursor goes between egui's windows and its tooltips
+					EguiLayerMap::new(LayerId::Egui as u8)
+						.with_order(egui::Order::Tooltip, LayerId::EguiTooltip as u8)
+						.with_order(egui::Order::Debug, LayerId::EguiTooltip as u8),
+				)
+				.with_egui_effect(EffectId::Egui as u16)
+				.with_overlay(EguiOverlay::default().with_hotkey(b'`').with_visible(true));
+			self.egui_wrapper.configure(config, renderer)?;
+			self.egui_wrapper
+				.load_theme(&mut self.system, "theme.ron")?;
+			self.egui_wrapper
+				.load_memory(&mut self.system, "egui_memory.ron");
+		}
 
 		self.font_size = 10;
 
+		self.telemetry.set_threshold("target_frame_time", true);
+		self.telemetry
+			.set_budget("time_step", Some("target_frame_time"));
+		self.telemetry.arm_trigger(
+			EguiTrigger::new("time_step", EguiTriggerCondition::Above, 0.05)
+				.with_pre_frames(60)
+				.with_post_frames(60),
+		);
+		if let Err(e) = self
+			.telemetry
+			.load_config(&mut self.system, "telemetry.ron")
+		{
+			tracing::info!("No telemetry config loaded: {}", &e);
+		}
 		oml_game::DefaultTelemetry::enable();
 		Ok(())
 	}
 
 	fn teardown(&mut self) {
+		if let Err(e) = self
+			.egui_wrapper
+			.save_memory(&mut self.system, "egui_memory.ron")
+		{
+			tracing::warn!("Couldn't save egui memory: {}", &e);
+		}
+		if let Err(e) = self
+			.telemetry
+			.save_config(&mut self.system, "telemetry.ron")
+		{
+			tracing::warn!("Couldn't save telemetry config: {}", &e);
+		}
 		self.renderer = None;
 	}
 
@@ -154,7 +186,7 @@ impl App for MinimalApp {
 		if self.frame_count % 100 < 50 {
 			oml_game::DefaultTelemetry::trace::<f32>(
 				"sin of frame_count",
-				(1.0 / 60.0) * (self.frame_count as f32 * 0.01).sin() as f32,
+				(1.0 / 60.0) * (self.frame_count as f32 * 0.01).sin(),
 			);
 		}
 
@@ -208,52 +240,15 @@ impl App for MinimalApp {
 			renderer.update(&mut self.system);
 		}
 
-		self.egui_wrapper.update(wuc);
+		self.egui_wrapper.update(wuc)?;
 		self.egui_wrapper.run(&mut self.system, |ctx| {
-			ctx.set_visuals(egui::style::Visuals::light());
-			ctx.set_visuals(egui::style::Visuals::dark());
-
-			if true {
-				let mut style = (*ctx.style()).clone();
-				style.override_text_style = Some(
-					egui::TextStyle::Heading, //egui::FontId::new(30.0, egui::FontFamily::Proportional)
-				);
-
-				style.text_styles = [
-					(
-						egui::TextStyle::Heading,
-						egui::FontId::new(30.0, egui::FontFamily::Proportional),
-					),
-					(
-						egui::TextStyle::Name("Heading2".into()),
-						egui::FontId::new(25.0, egui::FontFamily::Proportional),
-					),
-					(
-						egui::TextStyle::Name("Context".into()),
-						egui::FontId::new(23.0, egui::FontFamily::Proportional),
-					),
-					(
-						egui::TextStyle::Body,
-						egui::FontId::new(18.0, egui::FontFamily::Proportional),
-					),
-					(
-						egui::TextStyle::Monospace,
-						egui::FontId::new(14.0, egui::FontFamily::Proportional),
-					),
-					(
-						egui::TextStyle::Button,
-						egui::FontId::new(14.0, egui::FontFamily::Proportional),
-					),
-					(
-						egui::TextStyle::Small,
-						//						egui::FontId::new(10.0, egui::FontFamily::Proportional),
-						egui::FontId::new(self.font_size as f32, egui::FontFamily::Proportional),
-					),
-				]
-				.into();
-
-				ctx.set_style(style);
-			}
+			// everything else comes from theme.ron
+			let mut style = (*ctx.style()).clone();
+			style.text_styles.insert(
+				egui::TextStyle::Small,
+				egui::FontId::new(self.font_size as f32, egui::FontFamily::Proportional),
+			);
+			ctx.set_style(style);
 
 			egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
 				// The top panel is often a good place for a menu bar:
@@ -287,9 +282,8 @@ impl App for MinimalApp {
 						.text_style(egui::style::TextStyle::Small)
 						.strong(),
 				);
-				if ui.button("Quit?").clicked() {}
+				let _ = ui.button("Quit?");
 
-				ui.checkbox(&mut self.use_blend_factors, "Blend Factors");
 				ui.checkbox(&mut self.cull_face, "Cull Face");
 
 				ui.image(
@@ -311,76 +305,6 @@ impl App for MinimalApp {
 					});
 			}
 			/*
-			{
-				egui::Window::new("Telemetry")
-					.default_width(1000.0)
-					//.resize(|r| r.default_width( 1000.0 ))
-					//.resizable(true)
-					.show(ctx, |ui| {
-						use egui::plot::{Line, Plot, PlotPoints};
-
-						let traces_info = oml_game::DefaultTelemetry::traces_info();
-						//tracing::debug!("Traces: {:#?}", traces_info);
-						let mut lines = Vec::new();
-						let mut vlines = Vec::new();
-						let maximum_length = oml_game::DefaultTelemetry::maximum_length() as f64;
-						let frames = oml_game::DefaultTelemetry::frames() as f64;
-						let gone_frames = (frames - maximum_length).max(0.0);
-						let x_offset = gone_frames;
-						{
-							let v = oml_game::DefaultTelemetry::get::<f32>("time_step");
-							let points: PlotPoints = v
-								.iter()
-								.enumerate()
-								.filter_map(|(i, f)| {
-									if let Some(f) = f {
-										Some([i as f64 + x_offset, *f as f64])
-									} else {
-										None
-									}
-								})
-								//.map(|(i, f)| [i as f64, *f as f64])
-								.collect();
-							let line = Line::new(points);
-							lines.push(("time_step", line));
-						}
-						{
-							let v = oml_game::DefaultTelemetry::get::<f32>("sin of frame_count");
-							let mut gap = false;
-							let points: PlotPoints = v
-								.iter()
-								.enumerate()
-								.filter_map(|(i, f)| {
-									let r = if let Some(f) = f {
-										Some([i as f64 + x_offset, *f as f64])
-									} else {
-										None
-									};
-									let prev_gap = gap;
-									gap = r.is_none();
-									if gap != prev_gap {
-										vlines.push(egui::widgets::plot::VLine::new(
-											i as f64 + x_offset,
-										));
-									}
-									r
-								})
-								.collect();
-							let line = Line::new(points);
-							lines.push(("sin of time_step", line));
-						}
-						Plot::new("time_step").view_aspect(2.0).show(ui, |plot_ui| {
-							for (_name, line) in lines {
-								plot_ui.line(line)
-							}
-							for vl in vlines {
-								plot_ui.vline(vl);
-							}
-						});
-					});
-			}
-			*/
-			/*
 			ctx.set_visuals(egui::style::Visuals::light());
 			egui::Window::new("My Window")
 				.resizable(true)
@@ -389,9 +313,11 @@ impl App for MinimalApp {
 				});
 			*/
 			Ok(())
-		});
+		})?;
 
 		oml_game::DefaultTelemetry::update();
+		// keeps the trigger watching while the overlay is closed
+		self.telemetry.update();
 		Ok(())
 	}
 
@@ -408,48 +334,18 @@ impl App for MinimalApp {
 			);
 			renderer.clear(&color);
 
-			//let scaling = 0.5;
-			//let scaling = 0.25;
-			//let scaling = 1.0;
-			//let scaling = self.scaling;
-			//let scaling = 1.0 / self.scaling;
-			let half_scaling = 0.5 / self.scaling;
-			//				dbg!(&scaling);
-			let left = -self.size.x * half_scaling;
-			let right = self.size.x * half_scaling;
-			let top = self.size.y * half_scaling;
-			let bottom = -self.size.y * half_scaling;
-			let near = 1.0;
-			let far = -1.0;
-
-			//tracing::debug!("x: {} - {}, y: {} - {}", left, right, bottom, top);
-
-			//				dbg!(&top,&bottom);
-
-			let mvp = Matrix44::ortho(left, right, bottom, top, near, far);
-
-			//				dbg!(&mvp);
-
-			renderer.set_mvp_matrix(&mvp);
+			// the cursor uses the same coordinates as egui
+			self.egui_wrapper.set_mvp_matrix(renderer);
 
 			//renderer.use_effect(EffectId::Textured as u16);
-			self.egui_wrapper.render(&mut self.system, renderer);
+			if let Err(e) = self.egui_wrapper.render(&mut self.system, renderer) {
+				tracing::warn!("Couldn't render egui: {}", &e);
+			}
 
 			renderer.use_layer(LayerId::Debug as u8);
 			renderer.use_effect(EffectId::Textured as u16);
 			renderer.use_texture("cursor");
-			renderer.find_effect_mut_and_then("ColoredTextured", |e| {
-				if self.use_blend_factors {
-					e.set_blend_func(
-						oml_game::renderer::BlendFactor::One,
-						oml_game::renderer::BlendFactor::OneMinusSrcAlpha,
-					);
-				} else {
-					e.set_blend_func(
-						oml_game::renderer::BlendFactor::SrcAlpha,
-						oml_game::renderer::BlendFactor::OneMinusSrcAlpha,
-					);
-				}
+			renderer.find_effect_mut_and_then(EguiEffect::NAME, |e| {
 				e.set_cull_face(self.cull_face);
 			});
 
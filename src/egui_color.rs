@@ -0,0 +1,281 @@
+use egui::Color32;
+use oml_game::renderer::Color;
+
+/// egui renders text a bit bolder than the raw coverage, this matches its own backends.
+const FONT_GAMMA: f32 = 0.55;
+
+/// How the framebuffer we paint into interprets colors.
+///
+/// egui itself blends in sRGB (gamma) space, so `Gamma` matches its reference output.
+/// Use `Linear` when the framebuffer does the sRGB conversion on write, and register the effect with the same color space.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EguiColorSpace {
+	#[default]
+	Gamma,
+	Linear,
+}
+
+impl EguiColorSpace {
+	/// Converts a premultiplied sRGB egui color into a premultiplied vertex color for this color space.
+	pub fn vertex_color(&self, color: &Color32) -> Color {
+		match self {
+			EguiColorSpace::Gamma => Color::from_rgba(
+				color.r() as f32 / 255.0,
+				color.g() as f32 / 255.0,
+				color.b() as f32 / 255.0,
+				color.a() as f32 / 255.0,
+			),
+			EguiColorSpace::Linear => {
+				let rgba = egui::Rgba::from(*color);
+				Color::from_rgba(rgba.r(), rgba.g(), rgba.b(), rgba.a())
+			},
+		}
+	}
+
+	/// Turns a straight alpha tint into a premultiplied one in this color space.
+	///
+	/// Multiplying a premultiplied color with the result scales both its color and coverage by the tint's alpha.
+	pub fn tint(&self, color: &Color) -> Color {
+		let (r, g, b) = match self {
+			EguiColorSpace::Gamma => (color.r, color.g, color.b),
+			EguiColorSpace::Linear => (
+				linear_from_gamma(color.r),
+				linear_from_gamma(color.g),
+				linear_from_gamma(color.b),
+			),
+		};
+		Color::from_rgba(r * color.a, g * color.a, b * color.a, color.a)
+	}
+}
+
+pub(crate) fn linear_from_gamma(c: f32) -> f32 {
+	if c <= 0.04045 {
+		c / 12.92
+	} else {
+		((c + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+/// Packs a premultiplied egui color into a canvas texel, red in the lowest byte.
+pub(crate) fn texel_from_color32(color: &Color32) -> u32 {
+	u32::from_le_bytes(color.to_array())
+}
+
+/// Premultiplied white, with the font coverage as alpha.
+pub(crate) fn color32_from_coverage(coverage: f32) -> Color32 {
+	let a = (coverage.powf(FONT_GAMMA) * 255.0).round() as u8;
+	Color32::from_rgba_premultiplied(a, a, a, a)
+}
+
+pub(crate) fn texel_from_coverage(coverage: f32) -> u32 {
+	texel_from_color32(&color32_from_coverage(coverage))
+}
+
+#[cfg(test)]
+mod tests {
+	use egui::epaint::image::ColorImage;
+	use egui::epaint::image::ImageDelta;
+	use egui::epaint::textures::TextureOptions;
+	use egui::epaint::ClippedShape;
+	use egui::epaint::Primitive;
+	use egui::epaint::Shape;
+	use egui::epaint::TessellationOptions;
+	use egui::pos2;
+	use egui::Rect;
+	use egui::TextureId;
+
+	use super::*;
+	use crate::egui_rasterizer::EguiRasterizer;
+
+	const SIZE: usize = 8;
+
+	/// The canvas path: tessellates like egui does, anti-aliasing included, and rasterizes the result in `color_space`.
+	fn render(shapes: Vec<Shape>, color_space: EguiColorSpace) -> EguiRasterizer {
+		render_tinted(shapes, color_space, &Color::white())
+	}
+
+	fn render_tinted(
+		shapes: Vec<Shape>,
+		color_space: EguiColorSpace,
+		tint: &Color,
+	) -> EguiRasterizer {
+		let mut rasterizer = EguiRasterizer::default();
+		rasterizer.update_textures(&egui::TexturesDelta {
+			set:  vec![(
+				TextureId::default(),
+				ImageDelta::full(
+					ColorImage::new([1, 1], Color32::WHITE),
+					TextureOptions::LINEAR,
+				),
+			)],
+			free: Vec::new(),
+		});
+		rasterizer.clear(SIZE, SIZE, color_space);
+
+		let screen = Rect::from_min_max(pos2(0.0, 0.0), pos2(SIZE as f32, SIZE as f32));
+		let shapes = shapes
+			.into_iter()
+			.map(|shape| ClippedShape(screen, shape))
+			.collect();
+		let primitives = egui::epaint::tessellate_shapes(
+			1.0,
+			TessellationOptions::default(),
+			[1, 1],
+			Vec::new(),
+			shapes,
+		);
+		let tint = color_space.tint(tint);
+		let tint = [tint.r, tint.g, tint.b, tint.a];
+		for primitive in primitives {
+			if let Primitive::Mesh(mesh) = &primitive.primitive {
+				rasterizer.draw_mesh(&primitive.clip_rect, mesh, 1.0, &tint);
+			}
+		}
+		rasterizer
+	}
+
+	fn assert_gray(rasterizer: &EguiRasterizer, x: usize, y: usize, expected: u8) {
+		let color = rasterizer.color32(x, y);
+		assert_eq!(color.a(), 255, "pixel {} {} is not opaque", x, y);
+		assert!(
+			(color.r() as i32 - expected as i32).abs() <= 1,
+			"pixel {} {} is {:?}, expected gray {}",
+			x,
+			y,
+			color,
+			expected
+		);
+	}
+
+	fn background(color: Color32) -> Shape {
+		Shape::rect_filled(
+			Rect::from_min_max(pos2(-1.0, -1.0), pos2(SIZE as f32 + 1.0, SIZE as f32 + 1.0)),
+			0.0,
+			color,
+		)
+	}
+
+	#[test]
+	fn blends_translucent_windows() {
+		let shapes = || {
+			vec![
+				background(Color32::WHITE),
+				Shape::rect_filled(
+					Rect::from_min_max(pos2(-1.0, -1.0), pos2(4.0, SIZE as f32 + 1.0)),
+					0.0,
+					Color32::from_black_alpha(128),
+				),
+			]
+		};
+
+		// egui's reference: blending in sRGB, half of white stays half
+		let gamma = render(shapes(), EguiColorSpace::Gamma);
+		assert_gray(&gamma, 1, 4, 127);
+		assert_gray(&gamma, 6, 4, 255);
+
+		// half the light of white, which looks much brighter
+		let linear = render(shapes(), EguiColorSpace::Linear);
+		assert_gray(&linear, 1, 4, 187);
+		assert_gray(&linear, 6, 4, 255);
+	}
+
+	#[test]
+	fn blends_anti_aliased_edges() {
+		// the feathered edge is centered on x = 4.5, right through the middle of the pixel in column 4
+		let shapes = || {
+			vec![
+				background(Color32::BLACK),
+				Shape::rect_filled(
+					Rect::from_min_max(pos2(-1.0, -1.0), pos2(4.5, SIZE as f32 + 1.0)),
+					0.0,
+					Color32::WHITE,
+				),
+			]
+		};
+
+		let gamma = render(shapes(), EguiColorSpace::Gamma);
+		assert_gray(&gamma, 3, 4, 255);
+		assert_gray(&gamma, 4, 4, 128);
+		assert_gray(&gamma, 5, 4, 0);
+
+		let linear = render(shapes(), EguiColorSpace::Linear);
+		assert_gray(&linear, 3, 4, 255);
+		assert_gray(&linear, 4, 4, 188);
+		assert_gray(&linear, 5, 4, 0);
+	}
+
+	#[test]
+	fn converts_vertex_colors() {
+		let color = Color32::from_rgba_premultiplied(128, 0, 255, 255);
+
+		let gamma = EguiColorSpace::Gamma.vertex_color(&color);
+		assert!((gamma.r - 128.0 / 255.0).abs() < 1e-6);
+		assert_eq!(gamma.b, 1.0);
+
+		let linear = EguiColorSpace::Linear.vertex_color(&color);
+		assert!((linear.r - 0.2158).abs() < 1e-3);
+		assert_eq!(linear.g, 0.0);
+		assert!((linear.b - 1.0).abs() < 1e-6);
+		assert_eq!(linear.a, 1.0);
+	}
+
+	/// The screen path: a vertex color times the tint, blended with `One, OneMinusSrcAlpha` onto an opaque gray,
+	/// and written to the framebuffer, which converts to sRGB on write in `Linear`.
+	fn screen_gray(color_space: EguiColorSpace, color: Color32, tint: &Color, dst: u8) -> u8 {
+		let src = color_space.vertex_color(&color) * color_space.tint(tint);
+		let dst = color_space.vertex_color(&Color32::from_gray(dst)).r;
+		let c = src.r + dst * (1.0 - src.a);
+		match color_space {
+			EguiColorSpace::Gamma => (c * 255.0).round() as u8,
+			EguiColorSpace::Linear => Color32::from(egui::Rgba::from_rgb(c, c, c)).r(),
+		}
+	}
+
+	#[test]
+	fn screen_path_blends_like_egui() {
+		let window = Color32::from_black_alpha(128);
+		let half_tint = Color::from_rgba(1.0, 1.0, 1.0, 0.5);
+
+		// a translucent window over white, see `blends_translucent_windows`
+		let gamma = screen_gray(EguiColorSpace::Gamma, window, &Color::white(), 255);
+		assert_eq!(gamma, 127);
+		let linear = screen_gray(EguiColorSpace::Linear, window, &Color::white(), 255);
+		assert_eq!(linear, 187);
+
+		// a white window over black, faded out halfway by the tint
+		let gamma = screen_gray(EguiColorSpace::Gamma, Color32::WHITE, &half_tint, 0);
+		assert_eq!(gamma, 128);
+		let linear = screen_gray(EguiColorSpace::Linear, Color32::WHITE, &half_tint, 0);
+		assert_eq!(linear, 188);
+	}
+
+	#[test]
+	fn canvas_matches_the_screen_path() {
+		let window = Color32::from_black_alpha(128);
+		let half_tint = Color::from_rgba(1.0, 1.0, 1.0, 0.5);
+		for color_space in [EguiColorSpace::Gamma, EguiColorSpace::Linear] {
+			let canvas = render(
+				vec![background(Color32::WHITE), background(window)],
+				color_space,
+			);
+			let screen = screen_gray(color_space, window, &Color::white(), 255);
+			assert_gray(&canvas, 4, 4, screen);
+
+			// the tint fades the black background too, which leaves the canvas translucent
+			let canvas = render_tinted(
+				vec![background(Color32::BLACK), background(Color32::WHITE)],
+				color_space,
+				&half_tint,
+			);
+			let screen = screen_gray(color_space, Color32::WHITE, &half_tint, 0);
+			let color = canvas.color32(4, 4);
+			assert!(
+				(color.r() as i32 - screen as i32).abs() <= 1,
+				"{:?}: canvas {:?}, screen {}",
+				color_space,
+				color,
+				screen
+			);
+		}
+	}
+}
@@ -0,0 +1,116 @@
+use oml_game::renderer::BlendFactor;
+use oml_game::renderer::Effect;
+use oml_game::renderer::Renderer;
+use oml_game::system::filesystem::Filesystem;
+use oml_game::system::filesystem_stream::FilesystemStream;
+use oml_game::system::filesystem_stream_empty::FilesystemStreamEmpty;
+use oml_game::system::filesystem_stream_memory::FilesystemStreamMemory;
+use oml_game::system::System;
+
+use crate::EguiColorSpace;
+
+const VERTEX_SHADER: &str = include_str!("shaders/egui_vs.glsl");
+const FRAGMENT_SHADER: &str = include_str!("shaders/egui_fs.glsl");
+const LINEAR_FRAGMENT_SHADER: &str = include_str!("shaders/egui_linear_fs.glsl");
+
+/// The effect egui output needs, with the shaders embedded in the crate.
+///
+/// Blends premultiplied alpha, and doesn't cull since egui doesn't care about winding.
+/// Vertex colors arrive premultiplied in the framebuffer's color space, converted by `EguiColorSpace::vertex_color`,
+/// while the `Linear` variant decodes the sRGB textures in its fragment shader.
+pub struct EguiEffect {}
+
+impl EguiEffect {
+	pub const NAME: &'static str = "Egui";
+	pub const LINEAR_NAME: &'static str = "Egui Linear";
+
+	/// The name the built-in effect is registered under for `color_space`.
+	pub fn name(color_space: EguiColorSpace) -> &'static str {
+		match color_space {
+			EguiColorSpace::Gamma => EguiEffect::NAME,
+			EguiColorSpace::Linear => EguiEffect::LINEAR_NAME,
+		}
+	}
+
+	/// The color space of a built-in effect name, `None` for custom effects.
+	pub fn color_space_of(name: &str) -> Option<EguiColorSpace> {
+		[EguiColorSpace::Gamma, EguiColorSpace::Linear]
+			.into_iter()
+			.find(|color_space| EguiEffect::name(*color_space) == name)
+	}
+
+	pub fn create(system: &mut System, effect_id: u16, color_space: EguiColorSpace) -> Effect {
+		let fragment_shader = match color_space {
+			EguiColorSpace::Gamma => FRAGMENT_SHADER,
+			EguiColorSpace::Linear => LINEAR_FRAGMENT_SHADER,
+		};
+
+		// oml-game only loads shaders by file name, so the embedded sources stand in for the default filesystem while it does
+		let shaders = EguiShaderFilesystem {
+			files: vec![
+				(EguiEffect::VERTEX_SHADER_NAME, VERTEX_SHADER),
+				(EguiEffect::FRAGMENT_SHADER_NAME, fragment_shader),
+			],
+		};
+		let filesystem = std::mem::replace(system.default_filesystem_mut(), Box::new(shaders));
+		let effect = Effect::create(
+			system,
+			effect_id,
+			EguiEffect::name(color_space),
+			EguiEffect::VERTEX_SHADER_NAME,
+			EguiEffect::FRAGMENT_SHADER_NAME,
+		);
+		*system.default_filesystem_mut() = filesystem;
+
+		effect
+			.with_cull_face(false)
+			.with_blend_func(BlendFactor::One, BlendFactor::OneMinusSrcAlpha)
+	}
+
+	/// Registers the effect for a gamma framebuffer, which matches egui's own backends.
+	pub fn register(system: &mut System, renderer: &mut Renderer, effect_id: u16) {
+		EguiEffect::register_for_color_space(system, renderer, effect_id, EguiColorSpace::Gamma);
+	}
+
+	/// Registers as `EguiEffect::name(color_space)`, `EguiWrapperConfig::validate` checks it against the configured color space.
+	pub fn register_for_color_space(
+		system: &mut System,
+		renderer: &mut Renderer,
+		effect_id: u16,
+		color_space: EguiColorSpace,
+	) {
+		renderer.register_effect(EguiEffect::create(system, effect_id, color_space));
+	}
+
+	const VERTEX_SHADER_NAME: &'static str = "oml-game-egui/egui_vs.glsl";
+	const FRAGMENT_SHADER_NAME: &'static str = "oml-game-egui/egui_fs.glsl";
+}
+
+/// Serves the embedded shader sources by name.
+struct EguiShaderFilesystem {
+	files: Vec<(&'static str, &'static str)>,
+}
+
+impl Filesystem for EguiShaderFilesystem {
+	fn open(&mut self, name: &str) -> Box<dyn FilesystemStream> {
+		match self.files.iter().find(|(n, _)| *n == name) {
+			Some((_, source)) => Box::new(FilesystemStreamMemory::open(
+				name,
+				&source.as_bytes().to_vec(),
+			)),
+			None => Box::new(FilesystemStreamEmpty::open(name)),
+		}
+	}
+
+	fn exists(&self, name: &str) -> bool {
+		self.files.iter().any(|(n, _)| *n == name)
+	}
+
+	fn name(&self) -> &str {
+		"oml-game-egui shaders"
+	}
+
+	fn filesystem_type(&self) -> &str {
+		"Memory"
+	}
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use oml_game::system::System;
+
+use crate::filesystem;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EguiFontPriority {
+	/// Tried before all fonts already in the family.
+	Highest,
+	/// Only used for glyphs the fonts already in the family don't have, e.g. CJK or icons.
+	Fallback,
+}
+
+/// A TTF/OTF font loaded from the oml-game filesystem.
+#[derive(Debug, Clone)]
+pub struct EguiFont {
+	name:     String,
+	path:     String,
+	families: Vec<(egui::FontFamily, EguiFontPriority)>,
+}
+
+impl EguiFont {
+	pub fn new(name: &str, path: &str) -> Self {
+		Self {
+			name:     name.to_string(),
+			path:     path.to_string(),
+			families: Vec::new(),
+		}
+	}
+
+	/// An icon font, used as fallback by both the proportional and the monospace family.
+	pub fn icons(name: &str, path: &str) -> Self {
+		Self::new(name, path)
+			.with_family(egui::FontFamily::Proportional, EguiFontPriority::Fallback)
+			.with_family(egui::FontFamily::Monospace, EguiFontPriority::Fallback)
+	}
+
+	/// Adds the font to `family`, custom families can be created via `egui::FontFamily::Name`.
+	pub fn with_family(mut self, family: egui::FontFamily, priority: EguiFontPriority) -> Self {
+		self.families.push((family, priority));
+		self
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn path(&self) -> &str {
+		&self.path
+	}
+}
+
+/// The fonts loaded on top of egui's own, and the bytes we got for them.
+#[derive(Debug, Default)]
+pub(crate) struct EguiFonts {
+	base:  Option<egui::FontDefinitions>,
+	fonts: Vec<(EguiFont, Vec<u8>)>,
+}
+
+impl EguiFonts {
+	pub fn set_base(&mut self, base: egui::FontDefinitions) {
+		self.base = Some(base);
+	}
+
+	pub fn load(&mut self, system: &mut System, font: EguiFont) -> anyhow::Result<()> {
+		let data = filesystem::read_bytes(system, font.path())?;
+		tracing::debug!(
+			"Loaded font {} from {} ({} bytes)",
+			font.name(),
+			font.path(),
+			data.len()
+		);
+		self.fonts.retain(|(f, _)| f.name() != font.name());
+		self.fonts.push((font, data));
+		Ok(())
+	}
+
+	/// Rereads all fonts, keeping the old data for fonts that fail to load.
+	pub fn reload(&mut self, system: &mut System) -> anyhow::Result<()> {
+		let mut failed = Vec::new();
+		for (font, data) in self.fonts.iter_mut() {
+			match filesystem::read_bytes(system, font.path()) {
+				Ok(d) => *data = d,
+				Err(e) => {
+					tracing::warn!("Couldn't reload font {}: {}", font.name(), &e);
+					failed.push(font.name().to_string());
+				},
+			}
+		}
+		if !failed.is_empty() {
+			anyhow::bail!("Couldn't reload fonts: {}", failed.join(", "));
+		}
+		Ok(())
+	}
+
+	pub fn remove(&mut self, name: &str) -> bool {
+		let len = self.fonts.len();
+		self.fonts.retain(|(f, _)| f.name() != name);
+		self.fonts.len() != len
+	}
+
+	/// `Highest` fonts go before the family's fonts in the order they were loaded, `Fallback` ones after them.
+	pub fn definitions(&self) -> egui::FontDefinitions {
+		let mut definitions = self.base.clone().unwrap_or_default();
+		let mut highest: HashMap<egui::FontFamily, usize> = HashMap::new();
+		for (font, data) in self.fonts.iter() {
+			definitions.font_data.insert(
+				font.name().to_string(),
+				egui::FontData::from_owned(data.clone()),
+			);
+			for (family, priority) in font.families.iter() {
+				let names = definitions.families.entry(family.clone()).or_default();
+				match priority {
+					EguiFontPriority::Highest => {
+						let index = highest.entry(family.clone()).or_default();
+						names.insert(*index, font.name().to_string());
+						*index += 1;
+					},
+					EguiFontPriority::Fallback => names.push(font.name().to_string()),
+				}
+			}
+		}
+		definitions
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn fonts(fonts: Vec<EguiFont>) -> EguiFonts {
+		let mut base = egui::FontDefinitions::empty();
+		base.families
+			.insert(egui::FontFamily::Proportional, vec!["base".to_string()]);
+		EguiFonts {
+			base:  Some(base),
+			fonts: fonts.into_iter().map(|f| (f, Vec::new())).collect(),
+		}
+	}
+
+	fn family(fonts: &EguiFonts, family: egui::FontFamily) -> Vec<String> {
+		fonts.definitions().families[&family].clone()
+	}
+
+	#[test]
+	fn highest_fonts_keep_their_load_order() {
+		let fonts = fonts(vec![
+			EguiFont::new("a", "a.ttf")
+				.with_family(egui::FontFamily::Proportional, EguiFontPriority::Highest),
+			EguiFont::new("b", "b.ttf")
+				.with_family(egui::FontFamily::Proportional, EguiFontPriority::Fallback),
+			EguiFont::new("c", "c.ttf")
+				.with_family(egui::FontFamily::Proportional, EguiFontPriority::Highest)
+				.with_family(egui::FontFamily::Monospace, EguiFontPriority::Highest),
+		]);
+
+		assert_eq!(
+			family(&fonts, egui::FontFamily::Proportional),
+			vec!["a", "c", "base", "b"]
+		);
+		assert_eq!(family(&fonts, egui::FontFamily::Monospace), vec!["c"]);
+	}
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use oml_game::math::Vector2;
+use oml_game::renderer::Color;
+
+use crate::EguiColorSpace;
+use crate::EguiRenderTarget;
+use crate::EguiScreen;
+
+/// Everything the render side needs to draw one egui frame.
+///
+/// Produced on the update thread by `EguiWrapper::run`, consumed on the render thread by `EguiWrapper::render`.
+#[derive(Debug, Default)]
+pub struct EguiFrame {
+	pub layers:            Vec<EguiFrameLayer>,
+	pub textures_delta:    egui::TexturesDelta,
+	pub effect_id:         u16,
+	pub color:             Color,
+	pub color_space:       EguiColorSpace,
+	pub render_target:     EguiRenderTarget,
+	pub pixels_per_point:  f32,
+	pub texture_namespace: String,
+	/// Renderer texture names, indexed by `egui::TextureId::User`.
+	pub user_textures:     Arc<Vec<String>>,
+	pub screen:            EguiScreen,
+}
+
+/// Primitives that go into one oml-game layer, in paint order.
+#[derive(Debug, Default)]
+pub struct EguiFrameLayer {
+	pub layer_id:           u8,
+	pub clipped_primitives: Vec<egui::ClippedPrimitive>,
+}
+
+/// Hands the latest `EguiFrame` from the update thread to the render thread.
+///
+/// Both sides only hold the lock for a swap.
+/// A frame that is replaced before it was rendered is dropped, but its texture updates are carried over.
+#[derive(Debug, Default)]
+pub(crate) struct EguiFrameExchange {
+	pending:       Mutex<Option<EguiFrame>>,
+	viewport_size: Mutex<Vector2>,
+}
+
+impl EguiFrameExchange {
+	pub fn publish(&self, mut frame: EguiFrame) {
+		let mut pending = self.pending.lock().unwrap();
+		if let Some(skipped) = pending.take() {
+			let mut textures_delta = skipped.textures_delta;
+			textures_delta.append(frame.textures_delta);
+			frame.textures_delta = textures_delta;
+		}
+		*pending = Some(frame);
+	}
+
+	/// Never blocks, if the update thread is publishing right now we just get the frame next time.
+	pub fn take(&self) -> Option<EguiFrame> {
+		match self.pending.try_lock() {
+			Ok(mut pending) => pending.take(),
+			Err(_) => None,
+		}
+	}
+
+	pub fn has_pending(&self) -> bool {
+		self.pending.lock().unwrap().is_some()
+	}
+
+	pub fn set_viewport_size(&self, size: &Vector2) {
+		*self.viewport_size.lock().unwrap() = *size;
+	}
+
+	pub fn viewport_size(&self) -> Vector2 {
+		*self.viewport_size.lock().unwrap()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn frame(effect_id: u16, set: u64, free: u64) -> EguiFrame {
+		let image = egui::ColorImage::new([1, 1], egui::Color32::WHITE);
+		let mut frame = EguiFrame {
+			effect_id,
+			..Default::default()
+		};
+		frame.textures_delta.set.push((
+			egui::TextureId::Managed(set),
+			egui::epaint::ImageDelta::full(image, egui::TextureOptions::default()),
+		));
+		frame
+			.textures_delta
+			.free
+			.push(egui::TextureId::Managed(free));
+		frame
+	}
+
+	#[test]
+	fn skipped_frames_keep_their_texture_updates() {
+		let frames = EguiFrameExchange::default();
+		assert!(frames.take().is_none());
+
+		frames.publish(frame(1, 10, 20));
+		frames.publish(frame(2, 11, 21));
+		assert!(frames.has_pending());
+
+		let frame = frames.take().unwrap();
+		assert_eq!(frame.effect_id, 2);
+		let set: Vec<_> = frame.textures_delta.set.iter().map(|(id, _)| *id).collect();
+		assert_eq!(
+			set,
+			vec![egui::TextureId::Managed(10), egui::TextureId::Managed(11)]
+		);
+		assert_eq!(
+			frame.textures_delta.free,
+			vec![egui::TextureId::Managed(20), egui::TextureId::Managed(21)]
+		);
+
+		assert!(!frames.has_pending());
+		assert!(frames.take().is_none());
+	}
+}
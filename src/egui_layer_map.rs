@@ -0,0 +1,207 @@
+use egui::epaint::ClippedShape;
+use egui::Order;
+
+const ORDERS: [Order; 6] = [
+	Order::Background,
+	Order::PanelResizeLine,
+	Order::Middle,
+	Order::Foreground,
+	Order::Tooltip,
+	Order::Debug,
+];
+
+/// Paint callback that marks where the shapes of an `Order` start, never actually painted.
+struct EguiOrderMarker(Order);
+
+fn marker_area_id(order: Order) -> egui::Id {
+	egui::Id::new(("oml-game-egui order marker", order_index(order)))
+}
+
+fn order_index(order: Order) -> usize {
+	match order {
+		Order::Background => 0,
+		Order::PanelResizeLine => 1,
+		Order::Middle => 2,
+		Order::Foreground => 3,
+		Order::Tooltip => 4,
+		Order::Debug => 5,
+	}
+}
+
+/// Which oml-game layer the output of each egui `Order` is drawn into.
+///
+/// Allows drawing game content between e.g. egui's background panels and its tooltips.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct EguiLayerMap {
+	layer_ids: [u8; 6],
+}
+
+impl EguiLayerMap {
+	/// Everything goes into `layer_id`.
+	pub fn new(layer_id: u8) -> Self {
+		Self {
+			layer_ids: [layer_id; 6],
+		}
+	}
+
+	pub fn with_order(mut self, order: Order, layer_id: u8) -> Self {
+		self.set_layer_id(order, layer_id);
+		self
+	}
+
+	pub fn set_layer_id(&mut self, order: Order, layer_id: u8) {
+		self.layer_ids[order_index(order)] = layer_id;
+	}
+
+	pub fn layer_id(&self, order: Order) -> u8 {
+		self.layer_ids[order_index(order)]
+	}
+
+	/// egui flattens all layers into one list of shapes, without telling us where each order starts.
+	///
+	/// egui 0.21 neither exposes the paint list of a layer, nor keeps the layer in `ClippedShape`,
+	/// so before running the app's ui we paint one marker into an area of each order.
+	/// Within an order the areas are drawn back to front, so the marker area has to be the bottom one,
+	/// which is restored here should e.g. restored memory have put another area below it.
+	pub(crate) fn add_order_markers(ctx: &egui::Context) {
+		for order in ORDERS {
+			egui::Area::new(marker_area_id(order))
+				.order(order)
+				.interactable(false)
+				.fixed_pos(egui::Pos2::ZERO)
+				.show(ctx, |ui| {
+					ui.painter().add(egui::epaint::PaintCallback {
+						rect:     egui::Rect::NOTHING,
+						callback: std::sync::Arc::new(EguiOrderMarker(order)),
+					});
+				});
+		}
+
+		let layer_ids: Vec<egui::LayerId> = ctx.memory(|m| m.layer_ids().collect());
+		for order in ORDERS {
+			let mut layers = layer_ids.iter().filter(|l| l.order == order);
+			if layers.next().map(|l| l.id) == Some(marker_area_id(order)) {
+				continue;
+			}
+			// moving all others to the top keeps their order, and takes effect next frame
+			for layer_id in layer_ids.iter().filter(|l| l.order == order) {
+				if layer_id.id != marker_area_id(order) {
+					ctx.move_to_top(*layer_id);
+				}
+			}
+		}
+	}
+
+	/// Splits the shapes at the order markers, and drops the markers.
+	///
+	/// Neighbouring orders that share a layer stay in one group to keep things simple for the painter.
+	pub(crate) fn split_shapes(&self, shapes: Vec<ClippedShape>) -> Vec<(u8, Vec<ClippedShape>)> {
+		let mut groups: Vec<(u8, Vec<ClippedShape>)> = Vec::new();
+		let mut layer_id = self.layer_ids[0];
+
+		for clipped_shape in shapes {
+			if let ClippedShape(_, egui::Shape::Callback(callback)) = &clipped_shape {
+				if let Some(EguiOrderMarker(order)) = callback.callback.downcast_ref() {
+					layer_id = self.layer_id(*order);
+					continue;
+				}
+			}
+			match groups.last_mut() {
+				Some((id, group)) if *id == layer_id => group.push(clipped_shape),
+				_ => groups.push((layer_id, vec![clipped_shape])),
+			}
+		}
+
+		groups
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn marker(order: Order) -> ClippedShape {
+		ClippedShape(
+			egui::Rect::EVERYTHING,
+			egui::Shape::Callback(egui::epaint::PaintCallback {
+				rect:     egui::Rect::NOTHING,
+				callback: std::sync::Arc::new(EguiOrderMarker(order)),
+			}),
+		)
+	}
+
+	/// A shape that can be told apart by its radius.
+	fn shape(n: usize) -> ClippedShape {
+		ClippedShape(
+			egui::Rect::EVERYTHING,
+			egui::Shape::circle_filled(egui::Pos2::ZERO, n as f32, egui::Color32::WHITE),
+		)
+	}
+
+	fn radii(shapes: &[ClippedShape]) -> Vec<usize> {
+		shapes
+			.iter()
+			.map(|s| match &s.1 {
+				egui::Shape::Circle(circle) => circle.radius as usize,
+				s => panic!("Unexpected shape {:?}", s),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn shapes_are_split_at_the_markers() {
+		let map = EguiLayerMap::new(0)
+			.with_order(Order::Middle, 1)
+			.with_order(Order::Foreground, 1)
+			.with_order(Order::Tooltip, 2)
+			.with_order(Order::Debug, 2);
+		let shapes = vec![
+			shape(1),
+			marker(Order::Background),
+			shape(2),
+			marker(Order::PanelResizeLine),
+			shape(3),
+			marker(Order::Middle),
+			shape(4),
+			marker(Order::Foreground),
+			shape(5),
+			marker(Order::Tooltip),
+			marker(Order::Debug),
+			shape(6),
+		];
+
+		let groups: Vec<(u8, Vec<usize>)> = map
+			.split_shapes(shapes)
+			.iter()
+			.map(|(layer_id, shapes)| (*layer_id, radii(shapes)))
+			.collect();
+		assert_eq!(
+			groups,
+			vec![(0, vec![1, 2, 3]), (1, vec![4, 5]), (2, vec![6])]
+		);
+	}
+
+	#[test]
+	fn orders_going_back_to_a_layer_start_a_new_group() {
+		let map = EguiLayerMap::new(0).with_order(Order::Middle, 1);
+		let shapes = vec![
+			marker(Order::Background),
+			shape(1),
+			marker(Order::Middle),
+			shape(2),
+			marker(Order::Foreground),
+			shape(3),
+		];
+
+		let groups: Vec<(u8, Vec<usize>)> = map
+			.split_shapes(shapes)
+			.iter()
+			.map(|(layer_id, shapes)| (*layer_id, radii(shapes)))
+			.collect();
+		assert_eq!(groups, vec![(0, vec![1]), (1, vec![2]), (0, vec![3])]);
+
+		assert!(map
+			.split_shapes(vec![marker(Order::Background), marker(Order::Middle)])
+			.is_empty());
+	}
+}
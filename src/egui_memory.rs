@@ -0,0 +1,112 @@
+use oml_game::system::System;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::filesystem;
+
+/// Bump whenever a saved file wouldn't be understood anymore, e.g. after an egui update changed `egui::Memory`.
+const MEMORY_FILE_VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct EguiMemoryFileHeader {
+	version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EguiMemoryFile {
+	version: u32,
+	memory:  egui::Memory,
+}
+
+/// Saves window positions, collapsed states, scroll positions, etc.
+pub(crate) fn save(ctx: &egui::Context, system: &mut System, path: &str) -> anyhow::Result<()> {
+	let file = EguiMemoryFile {
+		version: MEMORY_FILE_VERSION,
+		memory:  ctx.memory(|m| m.clone()),
+	};
+	let source = ron::to_string(&file)?;
+	filesystem::write_string(system, path, &source)?;
+	tracing::debug!("Saved egui memory to {}", path);
+	Ok(())
+}
+
+/// Restores what `save` wrote.
+///
+/// Missing, outdated, or broken files are not an error, they are reported and leave the memory untouched.
+/// Returns if the memory was restored.
+pub(crate) fn load(ctx: &egui::Context, system: &mut System, path: &str) -> bool {
+	let source = match filesystem::read_string(system, path) {
+		Ok(source) => source,
+		Err(e) => {
+			tracing::info!("No egui memory loaded from {}: {}", path, &e);
+			return false;
+		},
+	};
+
+	match ron::from_str::<EguiMemoryFileHeader>(&source) {
+		Ok(header) if header.version == MEMORY_FILE_VERSION => {},
+		Ok(header) => {
+			tracing::warn!(
+				"Ignoring egui memory {} with version {}, expected {}",
+				path,
+				header.version,
+				MEMORY_FILE_VERSION
+			);
+			return false;
+		},
+		Err(e) => {
+			tracing::warn!("Ignoring broken egui memory {}: {}", path, &e);
+			return false;
+		},
+	}
+
+	match ron::from_str::<EguiMemoryFile>(&source) {
+		Ok(file) => {
+			// the style isn't part of the saved memory, so keep the one the themes set up
+			let style = ctx.style();
+			ctx.memory_mut(|m| *m = file.memory);
+			ctx.set_style(style);
+			true
+		},
+		Err(e) => {
+			tracing::warn!("Ignoring broken egui memory {}: {}", path, &e);
+			false
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use oml_game::system::filesystem_disk::FilesystemDisk;
+
+	use super::*;
+	use crate::egui_theme::EguiTheme;
+
+	#[test]
+	fn loading_keeps_the_style() {
+		let dir =
+			std::env::temp_dir().join(format!("oml-game-egui-memory-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let mut fs = FilesystemDisk::new(dir.to_str().unwrap());
+		fs.enable_write();
+		let mut system = System::new();
+		system.set_default_filesystem(Box::new(fs));
+
+		let ctx = egui::Context::default();
+		let id = egui::Id::new("saved");
+		ctx.memory_mut(|m| m.data.insert_persisted(id, 42u32));
+		save(&ctx, &mut system, "memory.ron").unwrap();
+
+		ctx.memory_mut(|m| m.data.remove::<u32>(id));
+		let theme = EguiTheme::from_ron("(visuals: Some((dark_mode: false)))").unwrap();
+		ctx.set_style(theme.style(&ctx.style()));
+		assert!(load(&ctx, &mut system, "memory.ron"));
+		assert_eq!(
+			ctx.memory_mut(|m| m.data.get_persisted::<u32>(id)),
+			Some(42)
+		);
+		assert!(!ctx.style().visuals.dark_mode);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,93 @@
+use crate::EguiScreen;
+
+/// A dialog that dims everything behind it, including the game, and takes all pointer and keyboard input while shown.
+///
+/// Call `show` every frame inside `EguiWrapper::run` for as long as the dialog should stay open.
+/// While it is, `EguiWrapper::update` consumes the mouse buttons and keys, so the game doesn't see them.
+///
+/// ```ignore
+/// egui_wrapper.run(system, |ctx| {
+///     if self.confirm_quit {
+///         EguiModal::new("confirm quit").show(ctx, |ui| {
+///             ui.label("Really quit?");
+///             if ui.button("Yes").clicked() {
+///                 self.quit = true;
+///             }
+///             if ui.button("No").clicked() {
+///                 self.confirm_quit = false;
+///             }
+///         });
+///     }
+///     Ok(())
+/// })?;
+/// if !egui_wrapper.is_modal_active() {
+///     // update gameplay
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EguiModal {
+	id:             egui::Id,
+	backdrop_color: egui::Color32,
+}
+
+impl EguiModal {
+	pub fn new(id_source: impl std::hash::Hash) -> Self {
+		Self {
+			id:             egui::Id::new(id_source),
+			backdrop_color: egui::Color32::from_black_alpha(160),
+		}
+	}
+
+	pub fn with_backdrop_color(mut self, backdrop_color: egui::Color32) -> Self {
+		self.backdrop_color = backdrop_color;
+		self
+	}
+
+	/// Backdrop and dialog go into `Order::Foreground`, so map that to a layer above the game.
+	pub fn show<R>(&self, ctx: &egui::Context, add_contents: impl FnOnce(&mut egui::Ui) -> R) -> R {
+		let full_screen_rect = EguiScreen::full_screen_rect_of(ctx);
+
+		egui::Area::new(self.id.with("backdrop"))
+			.order(egui::Order::Foreground)
+			.fixed_pos(full_screen_rect.min)
+			.interactable(true)
+			.show(ctx, |ui| {
+				ui.set_clip_rect(full_screen_rect);
+				ui.painter()
+					.rect_filled(full_screen_rect, 0.0, self.backdrop_color);
+				// swallows clicks, so nothing behind the dialog reacts
+				ui.allocate_rect(full_screen_rect, egui::Sense::click_and_drag());
+			});
+
+		let r = egui::Area::new(self.id)
+			.order(egui::Order::Foreground)
+			.anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+			.show(ctx, |ui| {
+				egui::Frame::window(ui.style()).show(ui, add_contents).inner
+			});
+		// tooltips can't be interacted with, so the dialog stays in the backdrop's order, just above it
+		ctx.move_to_top(r.response.layer_id);
+		let r = r.inner;
+
+		EguiModal::mark_active(ctx);
+		r
+	}
+
+	fn active_id() -> egui::Id {
+		egui::Id::new("oml-game-egui modal active")
+	}
+
+	fn mark_active(ctx: &egui::Context) {
+		ctx.memory_mut(|m| m.data.insert_temp(EguiModal::active_id(), true));
+	}
+
+	pub(crate) fn reset_active(ctx: &egui::Context) {
+		ctx.memory_mut(|m| m.data.remove::<bool>(EguiModal::active_id()));
+	}
+
+	/// True if a modal was shown since the last `reset_active`.
+	pub(crate) fn is_active(ctx: &egui::Context) -> bool {
+		ctx.memory_mut(|m| m.data.get_temp::<bool>(EguiModal::active_id()))
+			.unwrap_or(false)
+	}
+}
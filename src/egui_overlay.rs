@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+/// Where an overlay is in its show/hide cycle.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EguiOverlayState {
+	#[default]
+	Hidden,
+	FadingIn,
+	Visible,
+	FadingOut,
+}
+
+/// Shows and hides a whole `EguiWrapper`, e.g. a debug overlay, with a fade instead of an instant pop.
+///
+/// The fade is applied on top of the wrapper color, and input is disabled while the overlay is hidden or fading out.
+#[derive(Debug, Clone)]
+pub struct EguiOverlay {
+	hotkey:        Option<u8>,
+	fade_duration: Duration,
+	state:         EguiOverlayState,
+	visibility:    f32,
+}
+
+impl Default for EguiOverlay {
+	fn default() -> Self {
+		Self {
+			hotkey:        None,
+			fade_duration: Duration::from_millis(200),
+			state:         EguiOverlayState::Hidden,
+			visibility:    0.0,
+		}
+	}
+}
+
+impl EguiOverlay {
+	/// Toggles the overlay when `key` is pressed, checked in `EguiWrapper::update`.
+	pub fn with_hotkey(mut self, key: u8) -> Self {
+		self.hotkey = Some(key);
+		self
+	}
+
+	/// A zero duration shows and hides instantly.
+	pub fn with_fade_duration(mut self, fade_duration: Duration) -> Self {
+		self.fade_duration = fade_duration;
+		self
+	}
+
+	/// Starts out fully visible instead of hidden.
+	pub fn with_visible(mut self, visible: bool) -> Self {
+		if visible {
+			self.state = EguiOverlayState::Visible;
+			self.visibility = 1.0;
+		} else {
+			self.state = EguiOverlayState::Hidden;
+			self.visibility = 0.0;
+		}
+		self
+	}
+
+	pub fn hotkey(&self) -> Option<u8> {
+		self.hotkey
+	}
+
+	pub fn state(&self) -> EguiOverlayState {
+		self.state
+	}
+
+	/// From 0.0 for hidden to 1.0 for fully visible.
+	pub fn visibility(&self) -> f32 {
+		self.visibility
+	}
+
+	pub fn show(&mut self) {
+		match self.state {
+			EguiOverlayState::Hidden | EguiOverlayState::FadingOut => {
+				self.state = EguiOverlayState::FadingIn;
+			},
+			EguiOverlayState::FadingIn | EguiOverlayState::Visible => {},
+		}
+	}
+
+	pub fn hide(&mut self) {
+		match self.state {
+			EguiOverlayState::Visible | EguiOverlayState::FadingIn => {
+				self.state = EguiOverlayState::FadingOut;
+			},
+			EguiOverlayState::FadingOut | EguiOverlayState::Hidden => {},
+		}
+	}
+
+	/// Returns true if the overlay is on its way in.
+	pub fn toggle(&mut self) -> bool {
+		if self.is_showing() {
+			self.hide();
+		} else {
+			self.show();
+		}
+		self.is_showing()
+	}
+
+	/// True while visible or fading in.
+	pub fn is_showing(&self) -> bool {
+		matches!(
+			self.state,
+			EguiOverlayState::FadingIn | EguiOverlayState::Visible
+		)
+	}
+
+	/// True if nothing needs to be drawn.
+	pub fn is_hidden(&self) -> bool {
+		self.state == EguiOverlayState::Hidden
+	}
+
+	pub fn advance(&mut self, time_step: f64) {
+		let step = if self.fade_duration.is_zero() {
+			1.0
+		} else {
+			(time_step / self.fade_duration.as_secs_f64()) as f32
+		};
+		match self.state {
+			EguiOverlayState::FadingIn => {
+				self.visibility = (self.visibility + step).min(1.0);
+				if self.visibility >= 1.0 {
+					self.state = EguiOverlayState::Visible;
+				}
+			},
+			EguiOverlayState::FadingOut => {
+				self.visibility = (self.visibility - step).max(0.0);
+				if self.visibility <= 0.0 {
+					self.state = EguiOverlayState::Hidden;
+				}
+			},
+			EguiOverlayState::Hidden | EguiOverlayState::Visible => {},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn zero_fade_duration_switches_on_the_next_advance() {
+		let mut overlay = EguiOverlay::default().with_fade_duration(Duration::ZERO);
+		assert!(overlay.is_hidden());
+		assert!(!overlay.is_showing());
+
+		overlay.show();
+		assert_eq!(overlay.state(), EguiOverlayState::FadingIn);
+		assert!(!overlay.is_hidden());
+		assert!(overlay.is_showing());
+		overlay.advance(0.0);
+		assert_eq!(overlay.state(), EguiOverlayState::Visible);
+		assert_eq!(overlay.visibility(), 1.0);
+
+		overlay.hide();
+		assert_eq!(overlay.state(), EguiOverlayState::FadingOut);
+		// still drawn while fading out, but on its way out
+		assert!(!overlay.is_hidden());
+		assert!(!overlay.is_showing());
+		overlay.advance(0.0);
+		assert_eq!(overlay.state(), EguiOverlayState::Hidden);
+		assert_eq!(overlay.visibility(), 0.0);
+	}
+
+	#[test]
+	fn reversing_mid_fade_continues_from_the_current_visibility() {
+		let mut overlay = EguiOverlay::default().with_fade_duration(Duration::from_secs(1));
+		assert!(overlay.toggle());
+		overlay.advance(0.25);
+		assert_eq!(overlay.state(), EguiOverlayState::FadingIn);
+		assert_eq!(overlay.visibility(), 0.25);
+
+		assert!(!overlay.toggle());
+		assert_eq!(overlay.state(), EguiOverlayState::FadingOut);
+		assert_eq!(overlay.visibility(), 0.25);
+		overlay.advance(0.125);
+		assert_eq!(overlay.visibility(), 0.125);
+
+		overlay.show();
+		overlay.advance(0.5);
+		assert_eq!(overlay.state(), EguiOverlayState::FadingIn);
+		assert_eq!(overlay.visibility(), 0.625);
+		overlay.advance(1.0);
+		assert_eq!(overlay.state(), EguiOverlayState::Visible);
+		assert_eq!(overlay.visibility(), 1.0);
+
+		// showing again doesn't restart the fade
+		overlay.show();
+		assert_eq!(overlay.state(), EguiOverlayState::Visible);
+	}
+
+	#[test]
+	fn starting_visible() {
+		let overlay = EguiOverlay::default().with_visible(true);
+		assert_eq!(overlay.state(), EguiOverlayState::Visible);
+		assert_eq!(overlay.visibility(), 1.0);
+		assert!(overlay.is_showing());
+		assert!(!overlay.is_hidden());
+	}
+}
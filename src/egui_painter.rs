@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use oml_game::math::Matrix32;
+use oml_game::math::Vector2;
+use oml_game::renderer::Renderer;
+use oml_game::renderer::Texture;
+use oml_game::system::System;
+
+use crate::egui_color::texel_from_color32;
+use crate::egui_color::texel_from_coverage;
+use crate::egui_frame::EguiFrame;
+use crate::egui_rasterizer::EguiRasterizer;
+use crate::EguiRenderTarget;
+
+/// Render side state of the `EguiWrapper`.
+///
+/// Only ever touched from the render thread.
+#[derive(Debug, Default)]
+pub(crate) struct EguiPainter {
+	frame:             EguiFrame,
+	textures:          HashMap<egui::epaint::TextureId, EguiPainterTexture>,
+	/// Renderer textures can't be unregistered, so the ones egui freed are reused for new textures.
+	free_textures:     Vec<EguiPainterTexture>,
+	/// Freed by the current frame, which may still paint with them.
+	frees_after_frame: Vec<egui::epaint::TextureId>,
+	/// Freed by an earlier frame.
+	frees:             Vec<egui::epaint::TextureId>,
+	rasterizer:        EguiRasterizer,
+	/// Name and size of the canvas texture we paint into.
+	canvas:            Option<(String, u32)>,
+	canvas_dirty:      bool,
+	/// Textures egui sent while we weren't painting into a canvas, which the rasterizer doesn't have.
+	textures_skipped:  bool,
+}
+
+/// A canvas texture in the renderer holding an egui texture in its top left corner.
+#[derive(Debug)]
+struct EguiPainterTexture {
+	name: String,
+	id:   u16,
+	size: u32,
+}
+
+impl EguiPainter {
+	pub fn set_frame(&mut self, frame: EguiFrame) {
+		self.frees.append(&mut self.frees_after_frame);
+		let mut textures_delta = std::mem::take(&mut self.frame.textures_delta);
+		textures_delta.append(frame.textures_delta);
+		self.frame = EguiFrame {
+			textures_delta,
+			..frame
+		};
+		self.canvas_dirty = true;
+	}
+
+	fn update_texture_from_image(
+		tex: &mut Texture,
+		ox: usize,
+		oy: usize,
+		image: &egui::epaint::image::ImageData,
+	) {
+		match image {
+			egui::epaint::image::ImageData::Color(color_image) => {
+				let mut p = Vector2::zero();
+				for y in 0..color_image.size[1] {
+					p.y = (oy + y) as f32;
+					for x in 0..color_image.size[0] {
+						p.x = (ox + x) as f32;
+						let color = &color_image.pixels[y * color_image.size[0] + x];
+						tex.set_texel(&p, texel_from_color32(color));
+					}
+				}
+			},
+			egui::epaint::image::ImageData::Font(font_image) => {
+				let mut p = Vector2::zero();
+				for y in 0..font_image.size[1] {
+					p.y = (oy + y) as f32;
+					for x in 0..font_image.size[0] {
+						p.x = (ox + x) as f32;
+						let coverage = font_image.pixels[y * font_image.size[0] + x];
+						tex.set_texel(&p, texel_from_coverage(coverage));
+					}
+				}
+			},
+		};
+	}
+
+	/// Renderer textures are global and found by name, so every texture we register gets a name of its own.
+	///
+	/// The namespace only tells the contexts apart when looking at the renderer textures.
+	fn texture_name(&self) -> String {
+		static NEXT_TEXTURE: AtomicU64 = AtomicU64::new(0);
+		let n = NEXT_TEXTURE.fetch_add(1, Ordering::Relaxed);
+		if self.frame.texture_namespace.is_empty() {
+			format!("egui-{}", n)
+		} else {
+			format!("egui-{}-{}", self.frame.texture_namespace, n)
+		}
+	}
+
+	fn update_textures(&mut self, renderer: &mut Renderer) {
+		let textures_delta = std::mem::take(&mut self.frame.textures_delta);
+		// the rasterizer keeps its own copies, which only canvases need
+		match self.frame.render_target {
+			EguiRenderTarget::Canvas { .. } => self.rasterizer.update_textures(&textures_delta),
+			EguiRenderTarget::Screen => self.textures_skipped |= !textures_delta.set.is_empty(),
+		}
+
+		let frees = std::mem::take(&mut self.frees);
+		self.rasterizer.free_textures(&frees);
+		for id in frees {
+			if let Some(texture) = self.textures.remove(&id) {
+				self.free_textures.push(texture);
+			}
+		}
+
+		for (id, image_delta) in &textures_delta.set {
+			if let egui::epaint::TextureId::User(_) = id {
+				tracing::warn!("Ignoring egui image for user texture {:?}", id);
+				continue;
+			}
+			let image = &image_delta.image;
+
+			if let Some(pos) = &image_delta.pos {
+				// update existing texture
+				match self.textures.get(id) {
+					Some(texture) => {
+						renderer.find_texture_mut_and_then(&texture.name, |tex| {
+							EguiPainter::update_texture_from_image(tex, pos[0], pos[1], image);
+							tex.queue_canvas_update();
+						});
+					},
+					None => tracing::warn!("Partial update of unknown texture {:?}", id),
+				}
+			} else {
+				// (re)create the texture, reusing the old one if the image still fits
+				let size = image.size()[0].max(image.size()[1]) as u32;
+				let texture = match self.textures.remove(id) {
+					Some(texture) if texture.size >= size => texture,
+					Some(texture) => {
+						self.free_textures.push(texture);
+						self.allocate_texture(renderer, size)
+					},
+					None => self.allocate_texture(renderer, size),
+				};
+
+				let sx = image.size()[0] as f32 / texture.size as f32;
+				let sy = image.size()[1] as f32 / texture.size as f32;
+				let mtx = Matrix32::identity().with_scaling_xy(sx, sy);
+				renderer.find_texture_mut_and_then(&texture.name, |tex| {
+					tex.clear();
+					tex.set_mtx(&mtx);
+					EguiPainter::update_texture_from_image(tex, 0, 0, image);
+					tex.queue_canvas_update();
+				});
+				self.textures.insert(*id, texture);
+			}
+		}
+
+		self.frees_after_frame.extend(textures_delta.free);
+	}
+
+	/// The smallest freed texture that fits `size`, or a new one.
+	fn allocate_texture(&mut self, renderer: &mut Renderer, size: u32) -> EguiPainterTexture {
+		let free = self
+			.free_textures
+			.iter()
+			.enumerate()
+			.filter(|(_, t)| t.size >= size)
+			.min_by_key(|(_, t)| t.size)
+			.map(|(i, _)| i);
+		if let Some(i) = free {
+			return self.free_textures.swap_remove(i);
+		}
+
+		let name = self.texture_name();
+		let id = renderer.register_texture(Texture::create_canvas(&name, size));
+		EguiPainterTexture { name, id, size }
+	}
+
+	pub fn paint(&mut self, _system: &mut System, renderer: &mut Renderer) -> anyhow::Result<()> {
+		// textures are only applied once, primitives are repainted until the next frame arrives
+		self.update_textures(renderer);
+
+		if let EguiRenderTarget::Canvas { name, size } = self.frame.render_target.clone() {
+			self.paint_canvas(renderer, &name, size);
+			return Ok(());
+		}
+
+		for layer in self.frame.layers.iter() {
+			renderer.use_layer(layer.layer_id);
+			renderer.use_effect(self.frame.effect_id);
+
+			for egui::ClippedPrimitive {
+				clip_rect: _,
+				primitive,
+			} in layer.clipped_primitives.iter()
+			{
+				//tracing::debug!("ClipRect: {:?}", clip_rect);
+				match primitive {
+					egui::epaint::Primitive::Mesh(mesh) => {
+						//tracing::debug!("Mesh: {:?}", &mesh );
+						self.paint_mesh(renderer, mesh)?;
+					},
+					p => {
+						tracing::warn!("Unsupported primitive {:?}", &p);
+					},
+				};
+			}
+		}
+
+		Ok(())
+	}
+
+	fn paint_canvas(&mut self, renderer: &mut Renderer, name: &str, size: u32) {
+		if self.canvas.as_ref() != Some(&(name.to_string(), size)) {
+			// textures can't be unregistered, so a known name keeps its canvas while the size fits,
+			// and only uses the top left corner, just like the egui textures
+			let found = renderer.find_texture_mut_and_then(name, |tex| {
+				if tex.width() < size {
+					// the old GL texture leaks, so grow in big steps
+					*tex = Texture::create_canvas(name, size.next_power_of_two());
+				}
+				tex.clear();
+				let scale = size as f32 / tex.width() as f32;
+				tex.set_mtx(&Matrix32::identity().with_scaling_xy(scale, scale));
+			});
+			if !found {
+				renderer.register_texture(Texture::create_canvas(name, size));
+			}
+			self.canvas = Some((name.to_string(), size));
+			self.canvas_dirty = true;
+		}
+		if self.textures_skipped {
+			self.textures_skipped = false;
+			tracing::warn!(
+				"Switched to canvas {} after egui sent textures, set the canvas target before the first run",
+				name
+			);
+		}
+		if !self.canvas_dirty {
+			return;
+		}
+		self.canvas_dirty = false;
+
+		let tint = self.frame.color_space.tint(&self.frame.color);
+		let tint = [tint.r, tint.g, tint.b, tint.a];
+		self.rasterizer
+			.clear(size as usize, size as usize, self.frame.color_space);
+		for layer in self.frame.layers.iter() {
+			for egui::ClippedPrimitive {
+				clip_rect,
+				primitive,
+			} in layer.clipped_primitives.iter()
+			{
+				match primitive {
+					egui::epaint::Primitive::Mesh(mesh) => {
+						self.rasterizer.draw_mesh(
+							clip_rect,
+							mesh,
+							self.frame.pixels_per_point,
+							&tint,
+						);
+					},
+					p => {
+						tracing::warn!("Unsupported primitive {:?}", &p);
+					},
+				}
+			}
+		}
+
+		let rasterizer = &self.rasterizer;
+		renderer.find_texture_mut_and_then(name, |tex| {
+			rasterizer.copy_to_texture(tex);
+			tex.queue_canvas_update();
+		});
+	}
+
+	fn paint_mesh(&self, renderer: &mut Renderer, mesh: &egui::epaint::Mesh) -> anyhow::Result<()> {
+		let mut vertice_map = HashMap::new();
+
+		//let size = renderer.size();
+		//let aspect_ratio = renderer.aspect_ratio();
+
+		//tracing::debug!("Size: {:?}", &size );
+		//tracing::debug!("Aspect Ratio: {:?}", &aspect_ratio );
+
+		match &mesh.texture_id {
+			id @ egui::epaint::TextureId::Managed(_) => match self.textures.get(id) {
+				Some(texture) => renderer.use_texture_id_in_channel(texture.id, 0),
+				None => return Ok(()),
+			},
+			egui::epaint::TextureId::User(uid) => match self.frame.user_textures.get(*uid as usize)
+			{
+				Some(name) => renderer.use_texture_in_channel(name, 0),
+				None => {
+					tracing::warn!(
+						"Unknown user texture {}, see EguiWrapper::register_user_texture",
+						uid
+					);
+					return Ok(());
+				},
+			},
+		}
+		//		renderer.render_textured_fullscreen_quad();
+
+		let tint = self.frame.color_space.tint(&self.frame.color);
+
+		for (i, v) in mesh.vertices.iter().enumerate() {
+			let vertex = self.frame.screen.vertex(&v.pos);
+
+			//tracing::debug!("TC {}, {}", v.uv.x, v.uv.y);
+			renderer.set_tex_coords(&Vector2::new(v.uv.x, v.uv.y /*/8.0*/));
+			let color = self.frame.color_space.vertex_color(&v.color) * tint;
+
+			renderer.set_color(&color);
+			let vi = renderer.add_vertex(&vertex);
+			vertice_map.insert(i, vi);
+			//tracing::debug!("{} -> {}, {:?}", i, vi, v.pos );
+		}
+
+		for t in mesh.indices.chunks(3) {
+			let tm: Vec<u32> = t
+				.iter()
+				.map(|i| *vertice_map.get(&(*i as usize)).unwrap())
+				.collect();
+			renderer.add_triangle(tm[0], tm[1], tm[2]);
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,474 @@
+use std::collections::HashMap;
+
+use egui::epaint::image::ImageData;
+use egui::epaint::Color32;
+use egui::epaint::Mesh;
+use egui::epaint::Rect;
+use egui::epaint::TextureId;
+use oml_game::math::Vector2;
+use oml_game::renderer::Texture;
+
+use crate::egui_color::color32_from_coverage;
+use crate::egui_color::linear_from_gamma;
+use crate::egui_color::texel_from_color32;
+use crate::EguiColorSpace;
+
+/// CPU side copy of an egui texture.
+#[derive(Debug, Default)]
+struct EguiImage {
+	size:   [usize; 2],
+	pixels: Vec<Color32>,
+}
+
+impl EguiImage {
+	fn from_image_data(image: &ImageData) -> Self {
+		match image {
+			ImageData::Color(color_image) => Self {
+				size:   color_image.size,
+				pixels: color_image.pixels.clone(),
+			},
+			ImageData::Font(font_image) => Self {
+				size:   font_image.size,
+				pixels: font_image
+					.pixels
+					.iter()
+					.map(|coverage| color32_from_coverage(*coverage))
+					.collect(),
+			},
+		}
+	}
+
+	fn blit(&mut self, pos: &[usize; 2], image: &EguiImage) {
+		for y in 0..image.size[1] {
+			let dy = pos[1] + y;
+			if dy >= self.size[1] {
+				break;
+			}
+			for x in 0..image.size[0] {
+				let dx = pos[0] + x;
+				if dx >= self.size[0] {
+					break;
+				}
+				self.pixels[dy * self.size[0] + dx] = image.pixels[y * image.size[0] + x];
+			}
+		}
+	}
+
+	fn texel(&self, x: usize, y: usize) -> [f32; 4] {
+		let x = x.min(self.size[0] - 1);
+		let y = y.min(self.size[1] - 1);
+		let c = self.pixels[y * self.size[0] + x];
+		[
+			c.r() as f32 / 255.0,
+			c.g() as f32 / 255.0,
+			c.b() as f32 / 255.0,
+			c.a() as f32 / 255.0,
+		]
+	}
+
+	/// Bilinear, clamped to the edges, just like the GPU would do it for egui's textures.
+	fn sample(&self, u: f32, v: f32) -> [f32; 4] {
+		if self.pixels.is_empty() {
+			return [0.0; 4];
+		}
+		let x = (u * self.size[0] as f32 - 0.5).max(0.0);
+		let y = (v * self.size[1] as f32 - 0.5).max(0.0);
+		let x0 = x.floor() as usize;
+		let y0 = y.floor() as usize;
+		let fx = x.fract();
+		let fy = y.fract();
+
+		let t00 = self.texel(x0, y0);
+		let t10 = self.texel(x0 + 1, y0);
+		let t01 = self.texel(x0, y0 + 1);
+		let t11 = self.texel(x0 + 1, y0 + 1);
+
+		let mut r = [0.0; 4];
+		for i in 0..4 {
+			let top = t00[i] + (t10[i] - t00[i]) * fx;
+			let bottom = t01[i] + (t11[i] - t01[i]) * fx;
+			r[i] = top + (bottom - top) * fy;
+		}
+		r
+	}
+}
+
+/// Paints egui meshes into a CPU framebuffer, for targets the renderer can't draw into directly.
+///
+/// Blends premultiplied colors in the given color space, sRGB for `Gamma` just like egui's own backends.
+/// `Linear` blends in linear space, and encodes the result back to sRGB, which is how all egui textures are stored.
+#[derive(Debug, Default)]
+pub(crate) struct EguiRasterizer {
+	width:       usize,
+	height:      usize,
+	color_space: EguiColorSpace,
+	pixels:      Vec<[f32; 4]>,
+	textures:    HashMap<TextureId, EguiImage>,
+}
+
+impl EguiRasterizer {
+	pub fn update_textures(&mut self, textures_delta: &egui::TexturesDelta) {
+		for (id, image_delta) in &textures_delta.set {
+			let image = EguiImage::from_image_data(&image_delta.image);
+			match &image_delta.pos {
+				Some(pos) => {
+					if let Some(texture) = self.textures.get_mut(id) {
+						texture.blit(pos, &image);
+					}
+				},
+				None => {
+					self.textures.insert(*id, image);
+				},
+			}
+		}
+	}
+
+	/// egui frees textures after the frame that last used them, so this comes after painting it.
+	pub fn free_textures(&mut self, ids: &[TextureId]) {
+		for id in ids {
+			self.textures.remove(id);
+		}
+	}
+
+	pub fn clear(&mut self, width: usize, height: usize, color_space: EguiColorSpace) {
+		self.width = width;
+		self.height = height;
+		self.color_space = color_space;
+		self.pixels.clear();
+		self.pixels.resize(width * height, [0.0; 4]);
+	}
+
+	/// `tint` is premultiplied, in the color space passed to `clear`.
+	pub fn draw_mesh(
+		&mut self,
+		clip_rect: &Rect,
+		mesh: &Mesh,
+		pixels_per_point: f32,
+		tint: &[f32; 4],
+	) {
+		let texture = match self.textures.get(&mesh.texture_id) {
+			Some(texture) => texture,
+			None => return,
+		};
+
+		let clip_min_x = (clip_rect.min.x * pixels_per_point).floor().max(0.0) as usize;
+		let clip_min_y = (clip_rect.min.y * pixels_per_point).floor().max(0.0) as usize;
+		let clip_max_x =
+			((clip_rect.max.x * pixels_per_point).ceil().max(0.0) as usize).min(self.width);
+		let clip_max_y =
+			((clip_rect.max.y * pixels_per_point).ceil().max(0.0) as usize).min(self.height);
+
+		for t in mesh.indices.chunks(3) {
+			if t.len() < 3 {
+				break;
+			}
+			let v = [
+				&mesh.vertices[t[0] as usize],
+				&mesh.vertices[t[1] as usize],
+				&mesh.vertices[t[2] as usize],
+			];
+			let p = [
+				(v[0].pos.x * pixels_per_point, v[0].pos.y * pixels_per_point),
+				(v[1].pos.x * pixels_per_point, v[1].pos.y * pixels_per_point),
+				(v[2].pos.x * pixels_per_point, v[2].pos.y * pixels_per_point),
+			];
+			let area = edge(p[0], p[1], p[2]);
+			if area.abs() < f32::EPSILON {
+				continue;
+			}
+			let colors = [
+				self.vertex_color(&v[0].color),
+				self.vertex_color(&v[1].color),
+				self.vertex_color(&v[2].color),
+			];
+
+			// pixels right on an edge go to just one of the triangles sharing it
+			let owns = [(p[1], p[2]), (p[2], p[0]), (p[0], p[1])].map(|(a, b)| {
+				if area > 0.0 {
+					owns_edge(a, b)
+				} else {
+					owns_edge(b, a)
+				}
+			});
+
+			let (min_x, min_y, max_x, max_y) = pixel_bounds(&p);
+
+			for y in min_y.max(clip_min_y)..max_y.min(clip_max_y) {
+				let py = y as f32 + 0.5;
+				for x in min_x.max(clip_min_x)..max_x.min(clip_max_x) {
+					let px = x as f32 + 0.5;
+					// dividing by the signed area makes this independent of the winding
+					let w0 = edge(p[1], p[2], (px, py)) / area;
+					let w1 = edge(p[2], p[0], (px, py)) / area;
+					let w2 = edge(p[0], p[1], (px, py)) / area;
+					if !covers(w0, owns[0]) || !covers(w1, owns[1]) || !covers(w2, owns[2]) {
+						continue;
+					}
+
+					let u = w0 * v[0].uv.x + w1 * v[1].uv.x + w2 * v[2].uv.x;
+					let tv = w0 * v[0].uv.y + w1 * v[1].uv.y + w2 * v[2].uv.y;
+					let mut texel = texture.sample(u, tv);
+					if self.color_space == EguiColorSpace::Linear {
+						// filtered first, decoded second, like the linear fragment shader does
+						for c in texel.iter_mut().take(3) {
+							*c = linear_from_gamma(*c);
+						}
+					}
+
+					let dst = &mut self.pixels[y * self.width + x];
+					let mut src = [0.0; 4];
+					for i in 0..4 {
+						let c = w0 * colors[0][i] + w1 * colors[1][i] + w2 * colors[2][i];
+						src[i] = c * texel[i] * tint[i];
+					}
+					for i in 0..4 {
+						dst[i] = src[i] + dst[i] * (1.0 - src[3]);
+					}
+				}
+			}
+		}
+	}
+
+	/// Copies the framebuffer into a canvas texture, top row first.
+	pub fn copy_to_texture(&self, tex: &mut Texture) {
+		let mut p = Vector2::zero();
+		for y in 0..self.height {
+			p.y = y as f32;
+			for x in 0..self.width {
+				p.x = x as f32;
+				tex.set_texel(&p, texel_from_color32(&self.color32(x, y)));
+			}
+		}
+	}
+
+	/// The framebuffer pixel at `x`, `y`, as premultiplied sRGB.
+	pub fn color32(&self, x: usize, y: usize) -> Color32 {
+		let c = self.pixels[y * self.width + x].map(|c| c.clamp(0.0, 1.0));
+		match self.color_space {
+			EguiColorSpace::Gamma => Color32::from_rgba_premultiplied(
+				(c[0] * 255.0).round() as u8,
+				(c[1] * 255.0).round() as u8,
+				(c[2] * 255.0).round() as u8,
+				(c[3] * 255.0).round() as u8,
+			),
+			EguiColorSpace::Linear => {
+				Color32::from(egui::Rgba::from_rgba_premultiplied(c[0], c[1], c[2], c[3]))
+			},
+		}
+	}
+
+	fn vertex_color(&self, color: &Color32) -> [f32; 4] {
+		let c = self.color_space.vertex_color(color);
+		[c.r, c.g, c.b, c.a]
+	}
+}
+
+fn edge(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+	(b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// If pixels exactly on the edge from `a` to `b` belong to the triangle, with the edges going around the positive area.
+///
+/// The neighbour sharing the edge goes the other way, so only one of them draws those pixels.
+fn owns_edge(a: (f32, f32), b: (f32, f32)) -> bool {
+	let dy = b.1 - a.1;
+	dy > 0.0 || (dy == 0.0 && b.0 < a.0)
+}
+
+fn covers(w: f32, owns_edge: bool) -> bool {
+	w > 0.0 || (w == 0.0 && owns_edge)
+}
+
+/// Pixels touched by the triangle `p`, the max is exclusive.
+fn pixel_bounds(p: &[(f32, f32); 3]) -> (usize, usize, usize, usize) {
+	let min_x = p[0].0.min(p[1].0).min(p[2].0).floor().max(0.0);
+	let min_y = p[0].1.min(p[1].1).min(p[2].1).floor().max(0.0);
+	let max_x = p[0].0.max(p[1].0).max(p[2].0).ceil().max(0.0);
+	let max_y = p[0].1.max(p[1].1).max(p[2].1).ceil().max(0.0);
+	(
+		min_x as usize,
+		min_y as usize,
+		max_x as usize,
+		max_y as usize,
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use egui::epaint::image::ColorImage;
+	use egui::epaint::image::ImageDelta;
+	use egui::epaint::textures::TextureOptions;
+	use egui::epaint::Vertex;
+	use egui::epaint::WHITE_UV;
+	use egui::pos2;
+
+	use super::*;
+
+	const NO_TINT: [f32; 4] = [1.0; 4];
+
+	fn rasterizer_with_texture(image: ColorImage) -> EguiRasterizer {
+		let mut rasterizer = EguiRasterizer::default();
+		rasterizer.update_textures(&egui::TexturesDelta {
+			set:  vec![(
+				TextureId::default(),
+				ImageDelta::full(image, TextureOptions::LINEAR),
+			)],
+			free: Vec::new(),
+		});
+		rasterizer
+	}
+
+	fn triangle(points: [(f32, f32); 3], color: Color32) -> Mesh {
+		let mut mesh = Mesh::default();
+		for (x, y) in points {
+			mesh.vertices.push(Vertex {
+				pos: pos2(x, y),
+				uv: WHITE_UV,
+				color,
+			});
+		}
+		mesh.add_triangle(0, 1, 2);
+		mesh
+	}
+
+	fn everything() -> Rect {
+		Rect::from_min_max(pos2(0.0, 0.0), pos2(100.0, 100.0))
+	}
+
+	#[test]
+	fn covers_pixels_with_their_center_inside() {
+		for points in [
+			[(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)],
+			// the other winding
+			[(0.0, 0.0), (0.0, 4.0), (4.0, 0.0)],
+		] {
+			let mut rasterizer = rasterizer_with_texture(ColorImage::new([1, 1], Color32::WHITE));
+			rasterizer.clear(4, 4, EguiColorSpace::Gamma);
+			rasterizer.draw_mesh(
+				&everything(),
+				&triangle(points, Color32::RED),
+				1.0,
+				&NO_TINT,
+			);
+
+			for y in 0..4 {
+				for x in 0..4 {
+					let expected = if x + y < 4 {
+						Color32::RED
+					} else {
+						Color32::TRANSPARENT
+					};
+					assert_eq!(rasterizer.color32(x, y), expected, "pixel {} {}", x, y);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn blends_pixels_on_shared_edges_once() {
+		let color = Color32::from_black_alpha(128);
+		let mut rasterizer = rasterizer_with_texture(ColorImage::new([1, 1], Color32::WHITE));
+		rasterizer.clear(4, 4, EguiColorSpace::Gamma);
+		// the diagonal runs right through the pixel centers
+		for points in [
+			[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)],
+			[(0.0, 0.0), (0.0, 4.0), (4.0, 4.0)],
+		] {
+			rasterizer.draw_mesh(&everything(), &triangle(points, color), 1.0, &NO_TINT);
+		}
+
+		for y in 0..4 {
+			for x in 0..4 {
+				assert_eq!(rasterizer.color32(x, y), color, "pixel {} {}", x, y);
+			}
+		}
+	}
+
+	#[test]
+	fn clips_and_scales_by_pixels_per_point() {
+		let mut rasterizer = rasterizer_with_texture(ColorImage::new([1, 1], Color32::WHITE));
+		rasterizer.clear(4, 4, EguiColorSpace::Gamma);
+		let mesh = triangle([(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)], Color32::RED);
+		let clip_rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+		rasterizer.draw_mesh(&clip_rect, &mesh, 2.0, &NO_TINT);
+
+		assert_eq!(rasterizer.color32(0, 0), Color32::RED);
+		assert_eq!(rasterizer.color32(1, 1), Color32::RED);
+		assert_eq!(rasterizer.color32(2, 0), Color32::TRANSPARENT);
+		assert_eq!(rasterizer.color32(0, 2), Color32::TRANSPARENT);
+	}
+
+	#[test]
+	fn skips_meshes_with_unknown_textures() {
+		let mut rasterizer = EguiRasterizer::default();
+		rasterizer.clear(2, 2, EguiColorSpace::Gamma);
+		let mesh = triangle([(0.0, 0.0), (2.0, 0.0), (0.0, 2.0)], Color32::RED);
+		rasterizer.draw_mesh(&everything(), &mesh, 1.0, &NO_TINT);
+
+		assert_eq!(rasterizer.color32(0, 0), Color32::TRANSPARENT);
+	}
+
+	#[test]
+	fn samples_bilinear_and_clamps_to_the_edges() {
+		let image = EguiImage {
+			size:   [2, 1],
+			pixels: vec![Color32::BLACK, Color32::WHITE],
+		};
+
+		assert_eq!(image.sample(0.25, 0.5), [0.0, 0.0, 0.0, 1.0]);
+		assert_eq!(image.sample(0.75, 0.5), [1.0, 1.0, 1.0, 1.0]);
+		assert_eq!(image.sample(0.5, 0.5), [0.5, 0.5, 0.5, 1.0]);
+		assert_eq!(image.sample(0.0, 0.5), [0.0, 0.0, 0.0, 1.0]);
+		assert_eq!(image.sample(1.0, 0.5), [1.0, 1.0, 1.0, 1.0]);
+	}
+
+	#[test]
+	fn multiplies_texture_vertex_color_and_tint() {
+		let mut rasterizer = rasterizer_with_texture(ColorImage::new(
+			[1, 1],
+			Color32::from_rgba_premultiplied(128, 128, 128, 255),
+		));
+		rasterizer.clear(1, 1, EguiColorSpace::Gamma);
+		let mesh = triangle([(0.0, 0.0), (2.0, 0.0), (0.0, 2.0)], Color32::WHITE);
+		rasterizer.draw_mesh(&everything(), &mesh, 1.0, &[1.0, 0.0, 0.0, 1.0]);
+
+		assert_eq!(
+			rasterizer.color32(0, 0),
+			Color32::from_rgba_premultiplied(128, 0, 0, 255)
+		);
+	}
+
+	#[test]
+	fn applies_partial_texture_updates() {
+		let mut rasterizer = rasterizer_with_texture(ColorImage::new([2, 2], Color32::BLACK));
+		rasterizer.update_textures(&egui::TexturesDelta {
+			set:  vec![(
+				TextureId::default(),
+				ImageDelta::partial(
+					[1, 1],
+					ColorImage::new([1, 1], Color32::WHITE),
+					TextureOptions::LINEAR,
+				),
+			)],
+			free: Vec::new(),
+		});
+		let texture = &rasterizer.textures[&TextureId::default()];
+
+		assert_eq!(texture.sample(0.25, 0.25), [0.0, 0.0, 0.0, 1.0]);
+		assert_eq!(texture.sample(0.75, 0.75), [1.0, 1.0, 1.0, 1.0]);
+	}
+
+	#[test]
+	fn forgets_freed_textures() {
+		let mut rasterizer = rasterizer_with_texture(ColorImage::new([1, 1], Color32::WHITE));
+		// freeing only happens once the frame is painted
+		rasterizer.update_textures(&egui::TexturesDelta {
+			set:  Vec::new(),
+			free: vec![TextureId::default()],
+		});
+		assert_eq!(rasterizer.textures.len(), 1);
+
+		rasterizer.free_textures(&[TextureId::default()]);
+		assert!(rasterizer.textures.is_empty());
+	}
+}
@@ -0,0 +1,13 @@
+/// Where the `EguiWrapper` paints to.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum EguiRenderTarget {
+	/// Straight into the current layers of the renderer.
+	#[default]
+	Screen,
+	/// Into a square canvas texture registered under `name`, which the game can put onto any geometry.
+	///
+	/// The egui screen covers the whole canvas, with `(0, 0)` at the top left of the texture.
+	/// Pointer input comes from `EguiWrapper::set_pointer_uv` instead of the mouse.
+	/// A texture already registered under `name` is reused while it's big enough, with its matrix scaled to `size`.
+	Canvas { name: String, size: u32 },
+}
@@ -0,0 +1,510 @@
+use oml_game::math::Matrix44;
+use oml_game::math::Vector2;
+
+/// Where `(0, 0)` of the egui layer ends up in render coordinates.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EguiOrigin {
+	/// In the center of the screen, with y pointing up.
+	#[default]
+	Centered,
+	/// In the top left corner, with y pointing down, just like egui itself.
+	TopLeft,
+}
+
+/// How a virtual resolution is scaled to the window.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EguiScalePolicy {
+	/// Everything is visible, with letterboxing on two sides.
+	#[default]
+	Fit,
+	/// The whole window is covered, cutting off two sides.
+	Fill,
+	/// Like `Fit`, but only whole multiples, for crisp pixel art.
+	IntegerScale,
+}
+
+/// Parts of the viewport the ui must stay out of, e.g. notches or TV overscan.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum EguiSafeArea {
+	/// Insets in viewport pixels.
+	Pixels {
+		left:   f32,
+		top:    f32,
+		right:  f32,
+		bottom: f32,
+	},
+	/// Insets as fraction of the viewport size.
+	Fraction {
+		left:   f32,
+		top:    f32,
+		right:  f32,
+		bottom: f32,
+	},
+}
+
+impl EguiSafeArea {
+	/// Left, top, right, bottom insets in points.
+	fn insets(&self, viewport_size: &Vector2, pixels_per_point: f32) -> [f32; 4] {
+		match self {
+			EguiSafeArea::Pixels {
+				left,
+				top,
+				right,
+				bottom,
+			} => [
+				left / pixels_per_point,
+				top / pixels_per_point,
+				right / pixels_per_point,
+				bottom / pixels_per_point,
+			],
+			EguiSafeArea::Fraction {
+				left,
+				top,
+				right,
+				bottom,
+			} => [
+				left * viewport_size.x,
+				top * viewport_size.y,
+				right * viewport_size.x,
+				bottom * viewport_size.y,
+			],
+		}
+	}
+}
+
+/// Where the egui screen sits in the viewport for one viewport size.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct EguiScreenLayout {
+	/// The pixels per point egui gets.
+	pub pixels_per_point: f32,
+	/// Size of the egui screen, in points.
+	pub size:             Vector2,
+	/// Offset of the egui screen from the top left of the viewport, in points, i.e. the letterbox.
+	pub offset:           Vector2,
+	/// Size of the whole viewport, in points.
+	pub viewport_size:    Vector2,
+	/// Top left of the safe part of the egui screen, in points relative to its top left.
+	pub safe_min:         Vector2,
+	/// Bottom right of the safe part of the egui screen, in points relative to its top left.
+	pub safe_max:         Vector2,
+}
+
+/// Owns the conventions between egui's points, the window, and render coordinates.
+///
+/// Render coordinates are in points, so the mvp from `mvp_matrix` needs to be used while rendering egui.
+#[derive(Debug, Default, Clone)]
+pub struct EguiScreen {
+	origin:             EguiOrigin,
+	custom_mvp:         Option<Matrix44>,
+	virtual_resolution: Option<(Vector2, EguiScalePolicy)>,
+	safe_area:          Option<EguiSafeArea>,
+}
+
+impl EguiScreen {
+	pub fn new(origin: EguiOrigin) -> Self {
+		Self {
+			origin,
+			..Default::default()
+		}
+	}
+
+	/// Replaces the mvp from `mvp_matrix`, for games with a special camera setup.
+	///
+	/// Pointer input still assumes that the egui screen covers the whole window.
+	pub fn with_custom_mvp(mut self, mvp: Matrix44) -> Self {
+		self.custom_mvp = Some(mvp);
+		self
+	}
+
+	/// Lays egui out in a fixed `size`, in points, no matter how big the window is.
+	///
+	/// The pixels per point passed to the `EguiWrapper` are ignored then.
+	pub fn with_virtual_resolution(mut self, size: &Vector2, policy: EguiScalePolicy) -> Self {
+		self.virtual_resolution = Some((*size, policy));
+		self
+	}
+
+	/// Shrinks the screen rect egui lays out in, the whole screen can still be painted via `background_painter`.
+	pub fn with_safe_area(mut self, safe_area: EguiSafeArea) -> Self {
+		self.safe_area = Some(safe_area);
+		self
+	}
+
+	pub fn origin(&self) -> EguiOrigin {
+		self.origin
+	}
+
+	pub fn layout(&self, viewport_size: &Vector2, pixels_per_point: f32) -> EguiScreenLayout {
+		let (pixels_per_point, size) = match &self.virtual_resolution {
+			Some((size, policy)) if size.x > 0.0 && size.y > 0.0 => {
+				let sx = viewport_size.x / size.x;
+				let sy = viewport_size.y / size.y;
+				let scale = match policy {
+					EguiScalePolicy::Fit => sx.min(sy),
+					EguiScalePolicy::Fill => sx.max(sy),
+					EguiScalePolicy::IntegerScale => sx.min(sy).floor().max(1.0),
+				};
+				(scale, *size)
+			},
+			_ => (
+				pixels_per_point,
+				Vector2::new(
+					viewport_size.x / pixels_per_point,
+					viewport_size.y / pixels_per_point,
+				),
+			),
+		};
+		let viewport_size = Vector2::new(
+			viewport_size.x / pixels_per_point,
+			viewport_size.y / pixels_per_point,
+		);
+		let offset = Vector2::new(
+			0.5 * (viewport_size.x - size.x),
+			0.5 * (viewport_size.y - size.y),
+		);
+
+		// the insets are relative to the viewport, which doesn't have to match the egui screen
+		let [left, top, right, bottom] = match &self.safe_area {
+			Some(safe_area) => safe_area.insets(&viewport_size, pixels_per_point),
+			None => [0.0; 4],
+		};
+		let safe_min = Vector2::new(
+			(left - offset.x).clamp(0.0, size.x),
+			(top - offset.y).clamp(0.0, size.y),
+		);
+		let safe_max = Vector2::new(
+			(viewport_size.x - right - offset.x).clamp(safe_min.x, size.x),
+			(viewport_size.y - bottom - offset.y).clamp(safe_min.y, size.y),
+		);
+
+		EguiScreenLayout {
+			pixels_per_point,
+			size,
+			offset,
+			viewport_size,
+			safe_min,
+			safe_max,
+		}
+	}
+
+	/// The whole egui screen, in points.
+	pub fn full_screen_rect(&self, layout: &EguiScreenLayout) -> egui::Rect {
+		let size = egui::vec2(layout.size.x, layout.size.y);
+		match self.origin {
+			EguiOrigin::Centered => egui::Rect::from_center_size(egui::Pos2::ZERO, size),
+			EguiOrigin::TopLeft => egui::Rect::from_min_size(egui::Pos2::ZERO, size),
+		}
+	}
+
+	/// The screen rect egui gets, in points, without the unsafe areas.
+	pub fn screen_rect(&self, layout: &EguiScreenLayout) -> egui::Rect {
+		let full = self.full_screen_rect(layout);
+		egui::Rect::from_min_max(
+			full.min + egui::vec2(layout.safe_min.x, layout.safe_min.y),
+			full.min + egui::vec2(layout.safe_max.x, layout.safe_max.y),
+		)
+	}
+
+	fn full_screen_rect_id() -> egui::Id {
+		egui::Id::new("oml-game-egui full screen rect")
+	}
+
+	pub(crate) fn store_full_screen_rect(ctx: &egui::Context, rect: egui::Rect) {
+		ctx.memory_mut(|m| m.data.insert_temp(EguiScreen::full_screen_rect_id(), rect));
+	}
+
+	/// The whole egui screen, including the unsafe areas that `ctx.screen_rect()` leaves out.
+	pub fn full_screen_rect_of(ctx: &egui::Context) -> egui::Rect {
+		ctx.memory_mut(|m| m.data.get_temp(EguiScreen::full_screen_rect_id()))
+			.unwrap_or_else(|| ctx.screen_rect())
+	}
+
+	/// A painter behind all windows that may paint into the unsafe areas too.
+	pub fn background_painter(ctx: &egui::Context) -> egui::Painter {
+		let mut painter = ctx.layer_painter(egui::LayerId::background());
+		painter.set_clip_rect(EguiScreen::full_screen_rect_of(ctx));
+		painter
+	}
+
+	/// Shades everything outside of the safe area, to check the layout.
+	pub(crate) fn paint_unsafe_area(ctx: &egui::Context) {
+		let full = EguiScreen::full_screen_rect_of(ctx);
+		let safe = ctx.screen_rect();
+		let color = egui::Color32::from_rgba_unmultiplied(255, 0, 0, 64);
+
+		let mut painter = ctx.layer_painter(egui::LayerId::debug());
+		painter.set_clip_rect(full);
+		let rects = [
+			egui::Rect::from_min_max(full.min, egui::pos2(full.max.x, safe.min.y)),
+			egui::Rect::from_min_max(egui::pos2(full.min.x, safe.max.y), full.max),
+			egui::Rect::from_min_max(
+				egui::pos2(full.min.x, safe.min.y),
+				egui::pos2(safe.min.x, safe.max.y),
+			),
+			egui::Rect::from_min_max(
+				egui::pos2(safe.max.x, safe.min.y),
+				egui::pos2(full.max.x, safe.max.y),
+			),
+		];
+		for rect in rects {
+			if rect.is_positive() {
+				painter.rect_filled(rect, 0.0, color);
+			}
+		}
+	}
+
+	/// Turns the normalized mouse position of the window, with y pointing up, into an egui position.
+	pub fn pointer_pos(&self, layout: &EguiScreenLayout, mouse_pos: &Vector2) -> egui::Pos2 {
+		let x = mouse_pos.x * layout.viewport_size.x;
+		let y = (1.0 - mouse_pos.y) * layout.viewport_size.y;
+		match self.origin {
+			EguiOrigin::Centered => egui::pos2(
+				x - 0.5 * layout.viewport_size.x,
+				y - 0.5 * layout.viewport_size.y,
+			),
+			EguiOrigin::TopLeft => egui::pos2(x - layout.offset.x, y - layout.offset.y),
+		}
+	}
+
+	/// Turns an egui position into render coordinates.
+	pub fn vertex(&self, pos: &egui::Pos2) -> Vector2 {
+		match self.origin {
+			EguiOrigin::Centered => Vector2::new(pos.x, -pos.y), // upside down :(
+			EguiOrigin::TopLeft => Vector2::new(pos.x, pos.y),
+		}
+	}
+
+	/// The mvp that maps render coordinates onto the viewport, including the letterbox.
+	pub fn mvp_matrix(&self, layout: &EguiScreenLayout) -> Matrix44 {
+		if let Some(mvp) = &self.custom_mvp {
+			return *mvp;
+		}
+		let w = layout.viewport_size.x;
+		let h = layout.viewport_size.y;
+		let near = 1.0;
+		let far = -1.0;
+		match self.origin {
+			EguiOrigin::Centered => {
+				Matrix44::ortho(-0.5 * w, 0.5 * w, -0.5 * h, 0.5 * h, near, far)
+			},
+			EguiOrigin::TopLeft => {
+				let o = &layout.offset;
+				Matrix44::ortho(-o.x, w - o.x, h - o.y, -o.y, near, far)
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn origins_place_the_screen() {
+		let viewport_size = Vector2::new(800.0, 600.0);
+
+		let screen = EguiScreen::new(EguiOrigin::Centered);
+		let layout = screen.layout(&viewport_size, 2.0);
+		assert_eq!(
+			screen.full_screen_rect(&layout),
+			egui::Rect::from_min_max(egui::pos2(-200.0, -150.0), egui::pos2(200.0, 150.0))
+		);
+		assert_eq!(
+			screen.pointer_pos(&layout, &Vector2::new(0.5, 0.5)),
+			egui::pos2(0.0, 0.0)
+		);
+		// the mouse has y pointing up, egui has it pointing down
+		assert_eq!(
+			screen.pointer_pos(&layout, &Vector2::new(0.0, 1.0)),
+			egui::pos2(-200.0, -150.0)
+		);
+		assert_eq!(
+			screen.vertex(&egui::pos2(10.0, 20.0)),
+			Vector2::new(10.0, -20.0)
+		);
+
+		let screen = EguiScreen::new(EguiOrigin::TopLeft);
+		let layout = screen.layout(&viewport_size, 2.0);
+		assert_eq!(
+			screen.full_screen_rect(&layout),
+			egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(400.0, 300.0))
+		);
+		assert_eq!(
+			screen.pointer_pos(&layout, &Vector2::new(0.0, 1.0)),
+			egui::pos2(0.0, 0.0)
+		);
+		assert_eq!(
+			screen.pointer_pos(&layout, &Vector2::new(1.0, 0.0)),
+			egui::pos2(400.0, 300.0)
+		);
+		assert_eq!(
+			screen.vertex(&egui::pos2(10.0, 20.0)),
+			Vector2::new(10.0, 20.0)
+		);
+	}
+	fn virtual_layout(
+		viewport_size: &Vector2,
+		size: &Vector2,
+		policy: EguiScalePolicy,
+	) -> EguiScreenLayout {
+		EguiScreen::new(EguiOrigin::TopLeft)
+			.with_virtual_resolution(size, policy)
+			.layout(viewport_size, 1.0)
+	}
+
+	#[test]
+	fn layout_without_virtual_resolution_covers_the_viewport() {
+		let layout = EguiScreen::default().layout(&Vector2::new(800.0, 600.0), 2.0);
+		assert_eq!(layout.pixels_per_point, 2.0);
+		assert_eq!(layout.size, Vector2::new(400.0, 300.0));
+		assert_eq!(layout.viewport_size, Vector2::new(400.0, 300.0));
+		assert_eq!(layout.offset, Vector2::zero());
+	}
+
+	#[test]
+	fn scale_policies_letterbox_or_crop() {
+		let size = Vector2::new(1000.0, 400.0);
+
+		let layout = virtual_layout(&Vector2::new(2000.0, 1000.0), &size, EguiScalePolicy::Fit);
+		assert_eq!(layout.pixels_per_point, 2.0);
+		assert_eq!(layout.size, size);
+		assert_eq!(layout.viewport_size, Vector2::new(1000.0, 500.0));
+		assert_eq!(layout.offset, Vector2::new(0.0, 50.0));
+
+		let layout = virtual_layout(&Vector2::new(2000.0, 1000.0), &size, EguiScalePolicy::Fill);
+		assert_eq!(layout.pixels_per_point, 2.5);
+		assert_eq!(layout.viewport_size, Vector2::new(800.0, 400.0));
+		assert_eq!(layout.offset, Vector2::new(-100.0, 0.0));
+
+		let layout = virtual_layout(
+			&Vector2::new(2500.0, 1000.0),
+			&size,
+			EguiScalePolicy::IntegerScale,
+		);
+		assert_eq!(layout.pixels_per_point, 2.0);
+		assert_eq!(layout.viewport_size, Vector2::new(1250.0, 500.0));
+		assert_eq!(layout.offset, Vector2::new(125.0, 50.0));
+
+		// never smaller than one pixel per point
+		let layout = virtual_layout(
+			&Vector2::new(500.0, 200.0),
+			&size,
+			EguiScalePolicy::IntegerScale,
+		);
+		assert_eq!(layout.pixels_per_point, 1.0);
+		assert_eq!(layout.offset, Vector2::new(-250.0, -100.0));
+	}
+
+	#[test]
+	fn pointer_ends_up_under_the_mouse() {
+		// what the mvp makes of a render position, in normalized device coordinates
+		fn ndc(mvp: &Matrix44, v: &Vector2) -> Vector2 {
+			let x = mvp[0] * v.x + mvp[4] * v.y + mvp[12];
+			let y = mvp[1] * v.x + mvp[5] * v.y + mvp[13];
+			let w = mvp[3] * v.x + mvp[7] * v.y + mvp[15];
+			Vector2::new(x / w, y / w)
+		}
+
+		let viewport_size = Vector2::new(2000.0, 1000.0);
+		for origin in [EguiOrigin::Centered, EguiOrigin::TopLeft] {
+			for (size, policy) in [
+				(None, EguiScalePolicy::Fit),
+				(Some(Vector2::new(1000.0, 400.0)), EguiScalePolicy::Fit),
+				(Some(Vector2::new(1000.0, 400.0)), EguiScalePolicy::Fill),
+			] {
+				let mut screen = EguiScreen::new(origin);
+				if let Some(size) = size {
+					screen = screen.with_virtual_resolution(&size, policy);
+				}
+				let layout = screen.layout(&viewport_size, 2.0);
+				let mvp = screen.mvp_matrix(&layout);
+				for mouse_pos in [
+					Vector2::new(0.0, 0.0),
+					Vector2::new(0.25, 0.75),
+					Vector2::new(0.5, 0.5),
+					Vector2::new(1.0, 1.0),
+				] {
+					let pos = screen.pointer_pos(&layout, &mouse_pos);
+					let v = ndc(&mvp, &screen.vertex(&pos));
+					let expected = Vector2::new(2.0 * mouse_pos.x - 1.0, 2.0 * mouse_pos.y - 1.0);
+					assert!(
+						(v.x - expected.x).abs() < 1e-5 && (v.y - expected.y).abs() < 1e-5,
+						"{:?} {:?} at {:?}: {:?} instead of {:?}",
+						origin,
+						policy,
+						mouse_pos,
+						v,
+						expected
+					);
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn custom_mvp_is_used_as_is() {
+		let mvp = Matrix44::scaling(0.5);
+		let screen = EguiScreen::default().with_custom_mvp(mvp);
+		let layout = screen.layout(&Vector2::new(800.0, 600.0), 1.0);
+		assert!(screen.mvp_matrix(&layout) == mvp);
+	}
+	#[test]
+	fn safe_area_insets_in_pixels_and_fractions() {
+		let viewport_size = Vector2::new(1000.0, 500.0);
+
+		let screen = EguiScreen::new(EguiOrigin::TopLeft).with_safe_area(EguiSafeArea::Pixels {
+			left:   100.0,
+			top:    0.0,
+			right:  20.0,
+			bottom: 50.0,
+		});
+		let layout = screen.layout(&viewport_size, 2.0);
+		assert_eq!(layout.safe_min, Vector2::new(50.0, 0.0));
+		assert_eq!(layout.safe_max, Vector2::new(490.0, 225.0));
+		assert_eq!(
+			screen.screen_rect(&layout),
+			egui::Rect::from_min_max(egui::pos2(50.0, 0.0), egui::pos2(490.0, 225.0))
+		);
+
+		let screen = EguiScreen::new(EguiOrigin::Centered).with_safe_area(EguiSafeArea::Fraction {
+			left:   0.1,
+			top:    0.2,
+			right:  0.0,
+			bottom: 0.0,
+		});
+		let layout = screen.layout(&viewport_size, 2.0);
+		assert_eq!(layout.safe_min, Vector2::new(50.0, 50.0));
+		assert_eq!(layout.safe_max, Vector2::new(500.0, 250.0));
+		assert_eq!(
+			screen.screen_rect(&layout),
+			egui::Rect::from_min_max(egui::pos2(-200.0, -75.0), egui::pos2(250.0, 125.0))
+		);
+	}
+
+	#[test]
+	fn safe_area_is_clamped_to_the_screen() {
+		// letterboxed by 50 points on top and bottom, which already covers smaller insets
+		let screen = EguiScreen::new(EguiOrigin::TopLeft)
+			.with_virtual_resolution(&Vector2::new(1000.0, 400.0), EguiScalePolicy::Fit)
+			.with_safe_area(EguiSafeArea::Pixels {
+				left:   0.0,
+				top:    40.0,
+				right:  0.0,
+				bottom: 200.0,
+			});
+		let layout = screen.layout(&Vector2::new(2000.0, 1000.0), 1.0);
+		assert_eq!(layout.safe_min, Vector2::new(0.0, 0.0));
+		assert_eq!(layout.safe_max, Vector2::new(1000.0, 350.0));
+
+		// insets bigger than the screen leave an empty safe area instead of a negative one
+		let screen = EguiScreen::default().with_safe_area(EguiSafeArea::Fraction {
+			left:   0.6,
+			top:    0.0,
+			right:  0.6,
+			bottom: 1.5,
+		});
+		let layout = screen.layout(&Vector2::new(1000.0, 500.0), 1.0);
+		assert_eq!(layout.safe_min, Vector2::new(600.0, 0.0));
+		assert_eq!(layout.safe_max, Vector2::new(600.0, 0.0));
+	}
+}
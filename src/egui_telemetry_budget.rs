@@ -0,0 +1,101 @@
+/// Frames where `ys` is above the limit from `limits`.
+///
+/// A frame without a limit sample uses the last one before it, so sparse thresholds still work.
+pub(crate) fn violations(ys: &[Option<f64>], limits: &[Option<f64>]) -> Vec<usize> {
+	let mut violations = Vec::new();
+	let mut limit = None;
+	for (i, y) in ys.iter().enumerate() {
+		if let Some(Some(l)) = limits.get(i) {
+			limit = Some(*l);
+		}
+		if let (Some(y), Some(limit)) = (y, limit) {
+			if *y > limit {
+				violations.push(i);
+			}
+		}
+	}
+	violations
+}
+
+/// Runs of consecutive frames in `violations`, as `start..end` frame ranges.
+pub(crate) fn spans(violations: &[usize]) -> Vec<std::ops::Range<usize>> {
+	let mut spans: Vec<std::ops::Range<usize>> = Vec::new();
+	for v in violations {
+		match spans.last_mut() {
+			Some(span) if span.end == *v => span.end = v + 1,
+			_ => spans.push(*v..v + 1),
+		}
+	}
+	spans
+}
+
+/// Counts budget violations over the whole session, even after they left the telemetry buffer.
+#[derive(Debug, Default)]
+pub(crate) struct EguiBudgetCounter {
+	next_frame: u64,
+	total:      usize,
+}
+
+impl EguiBudgetCounter {
+	/// `violations` are indices into a buffer that starts at `first_frame` and holds `len` frames.
+	pub fn update(&mut self, violations: &[usize], first_frame: u64, len: usize) {
+		let next_frame = self.next_frame;
+		self.total += violations
+			.iter()
+			.filter(|v| first_frame + **v as u64 >= next_frame)
+			.count();
+		self.next_frame = self.next_frame.max(first_frame + len as u64);
+	}
+
+	pub fn total(&self) -> usize {
+		self.total
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn violations_carry_sparse_limits_forward() {
+		let ys = [Some(5.0), Some(5.0), Some(5.0), None, Some(3.0), Some(3.0)];
+		// nothing is over budget before the first limit
+		let limits = [None, Some(4.0), None, None, Some(2.0), None];
+		assert_eq!(violations(&ys, &limits), vec![1, 2, 4, 5]);
+	}
+
+	#[test]
+	fn violations_of_empty_or_limitless_input() {
+		assert!(violations(&[], &[]).is_empty());
+		assert!(violations(&[Some(1.0), Some(2.0)], &[]).is_empty());
+		// equal to the limit is still within budget
+		assert!(violations(&[Some(2.0), Some(2.0)], &[Some(2.0)]).is_empty());
+	}
+
+	#[test]
+	fn spans_join_consecutive_frames() {
+		assert!(spans(&[]).is_empty());
+		assert_eq!(spans(&[3]), vec![3..4]);
+		assert_eq!(spans(&[1, 2, 3, 5, 7, 8]), vec![1..4, 5..6, 7..9]);
+	}
+
+	#[test]
+	fn counter_counts_each_frame_once_as_the_buffer_rolls_over() {
+		let mut counter = EguiBudgetCounter::default();
+		// frames 0..4, over budget in 1 and 3
+		counter.update(&[1, 3], 0, 4);
+		assert_eq!(counter.total(), 2);
+
+		// the same buffer again, e.g. while paused
+		counter.update(&[1, 3], 0, 4);
+		assert_eq!(counter.total(), 2);
+
+		// frames 2..6, 3 was counted already, 5 is new
+		counter.update(&[1, 3], 2, 4);
+		assert_eq!(counter.total(), 3);
+
+		// frames 10..14, the frames in between were never seen
+		counter.update(&[0], 10, 4);
+		assert_eq!(counter.total(), 4);
+	}
+}
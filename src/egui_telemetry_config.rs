@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+
+use oml_game::system::System;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::egui_telemetry_widget::TraceConfig;
+use crate::filesystem;
+
+/// What `EguiTelemetryWidget::save_config` writes, the current trace settings and all named presets.
+///
+/// ```ron
+/// (
+///     traces: {
+///         "time_step": (enabled: true, color: (255, 215, 0, 255)),
+///     },
+///     presets: {
+///         "frame timing": {
+///             "time_step": (enabled: true, color: (255, 215, 0, 255)),
+///             "target_frame_time": (enabled: true, color: (64, 64, 64, 255)),
+///         },
+///     },
+/// )
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct EguiTelemetryConfig {
+	pub traces:  BTreeMap<String, TraceConfig>,
+	pub presets: BTreeMap<String, BTreeMap<String, TraceConfig>>,
+}
+
+impl EguiTelemetryConfig {
+	pub fn save(&self, system: &mut System, path: &str) -> anyhow::Result<()> {
+		let source = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+		filesystem::write_string(system, path, &source)?;
+		tracing::debug!("Saved telemetry config to {}", path);
+		Ok(())
+	}
+
+	pub fn load(system: &mut System, path: &str) -> anyhow::Result<Self> {
+		let source = filesystem::read_string(system, path)?;
+		Ok(ron::from_str(&source)?)
+	}
+}
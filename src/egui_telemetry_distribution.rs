@@ -0,0 +1,124 @@
+/// What the telemetry widget plots.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EguiTelemetryView {
+	/// Samples over frames.
+	#[default]
+	Lines,
+	/// How often values fall into each bin.
+	Histogram,
+	/// Median, quartiles, and extremes.
+	BoxPlot,
+	/// Fraction of samples at or below each value.
+	Cdf,
+}
+
+/// One histogram bin, `count` samples in `start..start + width`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct EguiHistogramBin {
+	pub start: f64,
+	pub width: f64,
+	pub count: usize,
+}
+
+/// Spreads `sorted` evenly over `bins` bins between its min and max.
+pub(crate) fn histogram(sorted: &[f64], bins: usize) -> Vec<EguiHistogramBin> {
+	let (min, max) = match (sorted.first(), sorted.last()) {
+		(Some(min), Some(max)) => (*min, *max),
+		_ => return Vec::new(),
+	};
+	let bins = bins.max(1);
+	// all samples equal still gets a visible bar
+	let width = if max > min {
+		(max - min) / bins as f64
+	} else {
+		1.0
+	};
+
+	let mut histogram: Vec<EguiHistogramBin> = (0..bins)
+		.map(|i| EguiHistogramBin {
+			start: min + i as f64 * width,
+			width,
+			count: 0,
+		})
+		.collect();
+	for v in sorted {
+		let i = (((v - min) / width) as usize).min(bins - 1);
+		histogram[i].count += 1;
+	}
+	histogram
+}
+
+/// Steps of the empirical distribution function of `sorted`.
+pub(crate) fn cdf_points(sorted: &[f64]) -> Vec<[f64; 2]> {
+	let n = sorted.len() as f64;
+	let mut points = Vec::with_capacity(2 * sorted.len());
+	for (i, v) in sorted.iter().enumerate() {
+		points.push([*v, i as f64 / n]);
+		points.push([*v, (i + 1) as f64 / n]);
+	}
+	points
+}
+
+/// Fraction of `sorted` at or below `value`.
+pub(crate) fn fraction_at_or_below(sorted: &[f64], value: f64) -> f64 {
+	if sorted.is_empty() {
+		return 0.0;
+	}
+	sorted.partition_point(|v| *v <= value) as f64 / sorted.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn histogram_spreads_samples_over_the_bins() {
+		let bins = histogram(&[0.0, 1.0, 1.5, 2.0, 4.0], 4);
+		let counts: Vec<usize> = bins.iter().map(|b| b.count).collect();
+		// the max lands in the last bin
+		assert_eq!(counts, vec![1, 2, 1, 1]);
+		assert_eq!(bins[0].start, 0.0);
+		assert_eq!(bins[3].start, 3.0);
+		assert!(bins.iter().all(|b| b.width == 1.0));
+	}
+
+	#[test]
+	fn histogram_of_empty_and_equal_samples() {
+		assert!(histogram(&[], 4).is_empty());
+
+		let bins = histogram(&[2.0, 2.0, 2.0], 4);
+		assert_eq!(bins[0].count, 3);
+		assert!(bins[0].width > 0.0);
+		assert!(bins[1..].iter().all(|b| b.count == 0));
+
+		// no bins still gets one
+		assert_eq!(histogram(&[1.0, 2.0], 0).len(), 1);
+	}
+
+	#[test]
+	fn cdf_steps_up_at_each_sample() {
+		assert!(cdf_points(&[]).is_empty());
+		assert_eq!(
+			cdf_points(&[1.0, 3.0]),
+			vec![[1.0, 0.0], [1.0, 0.5], [3.0, 0.5], [3.0, 1.0]]
+		);
+		assert_eq!(
+			cdf_points(&[2.0, 2.0]),
+			vec![[2.0, 0.0], [2.0, 0.5], [2.0, 0.5], [2.0, 1.0]]
+		);
+	}
+
+	#[test]
+	fn fraction_at_or_below_includes_equal_values() {
+		assert_eq!(fraction_at_or_below(&[], 1.0), 0.0);
+
+		let sorted = [1.0, 2.0, 2.0, 4.0];
+		assert_eq!(fraction_at_or_below(&sorted, 0.0), 0.0);
+		assert_eq!(fraction_at_or_below(&sorted, 2.0), 0.75);
+		assert_eq!(fraction_at_or_below(&sorted, 3.0), 0.75);
+		assert_eq!(fraction_at_or_below(&sorted, 4.0), 1.0);
+
+		assert_eq!(fraction_at_or_below(&[2.0, 2.0], 2.0), 1.0);
+		assert_eq!(fraction_at_or_below(&[2.0, 2.0], 1.9), 0.0);
+	}
+}
@@ -0,0 +1,139 @@
+use std::any::TypeId;
+use std::ops::Range;
+
+use oml_game::telemetry::TraceInfo;
+
+/// What the samples of a trace turned out to be.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EguiTraceKind {
+	Number,
+	/// A type the widget can't plot, like `String`.
+	Unsupported,
+}
+
+/// The samples of one trace, converted for plotting.
+#[derive(Debug)]
+pub(crate) struct EguiTraceSamples {
+	pub kind: EguiTraceKind,
+	pub ys:   Vec<Option<f64>>,
+}
+
+impl EguiTraceSamples {
+	/// The telemetry is typed, and only hands out samples for the type they were traced with.
+	pub fn load(trace_info: &TraceInfo) -> Self {
+		let name = trace_info.name();
+		let type_id = trace_info.entry_type_id();
+		let ys = if type_id == TypeId::of::<f32>() {
+			oml_game::DefaultTelemetry::get::<f32>(name)
+				.into_iter()
+				.map(|v| v.map(|v| v as f64))
+				.collect()
+		} else if type_id == TypeId::of::<f64>() {
+			oml_game::DefaultTelemetry::get::<f64>(name)
+		} else {
+			return EguiTraceSamples {
+				kind: EguiTraceKind::Unsupported,
+				ys:   Vec::new(),
+			};
+		};
+
+		EguiTraceSamples {
+			kind: EguiTraceKind::Number,
+			ys,
+		}
+	}
+
+	/// The points to plot, split at missing samples, `x_offset` is the number of frames that already left the buffer.
+	pub fn segments(&self, x_offset: f64) -> Vec<Vec<[f64; 2]>> {
+		let mut segments = Vec::new();
+		let mut segment = Vec::new();
+		for (i, y) in self.ys.iter().enumerate() {
+			let x = i as f64 + x_offset;
+			match (self.kind, y) {
+				(EguiTraceKind::Number, Some(y)) => segment.push([x, *y]),
+				_ => {
+					if !segment.is_empty() {
+						segments.push(std::mem::take(&mut segment));
+					}
+				},
+			}
+		}
+		if !segment.is_empty() {
+			segments.push(segment);
+		}
+		segments
+	}
+
+	/// Frames without a sample between the first and the last sample.
+	pub fn gaps(&self) -> Vec<Range<usize>> {
+		let mut gaps = Vec::new();
+		let mut last_sample = None;
+		for (i, y) in self.ys.iter().enumerate() {
+			if y.is_some() {
+				if let Some(last) = last_sample {
+					if i > last + 1 {
+						gaps.push(last + 1..i);
+					}
+				}
+				last_sample = Some(i);
+			}
+		}
+		gaps
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn samples(kind: EguiTraceKind, ys: &[Option<f64>]) -> EguiTraceSamples {
+		EguiTraceSamples {
+			kind,
+			ys: ys.to_vec(),
+		}
+	}
+
+	#[test]
+	fn segments_split_at_missing_samples() {
+		let s = samples(
+			EguiTraceKind::Number,
+			&[None, Some(1.0), Some(2.0), None, None, Some(3.0)],
+		);
+		assert_eq!(
+			s.segments(10.0),
+			vec![vec![[11.0, 1.0], [12.0, 2.0]], vec![[15.0, 3.0]]]
+		);
+
+		assert!(samples(EguiTraceKind::Number, &[]).segments(0.0).is_empty());
+		assert!(samples(EguiTraceKind::Number, &[None, None])
+			.segments(0.0)
+			.is_empty());
+		assert!(samples(EguiTraceKind::Unsupported, &[Some(1.0)])
+			.segments(0.0)
+			.is_empty());
+	}
+
+	#[test]
+	fn gaps_only_between_samples() {
+		let s = samples(
+			EguiTraceKind::Number,
+			&[
+				None,
+				Some(1.0),
+				None,
+				None,
+				Some(2.0),
+				Some(3.0),
+				None,
+				Some(4.0),
+				None,
+			],
+		);
+		assert_eq!(s.gaps(), vec![2..4, 6..7]);
+
+		assert!(samples(EguiTraceKind::Number, &[]).gaps().is_empty());
+		assert!(samples(EguiTraceKind::Number, &[None, Some(1.0), None])
+			.gaps()
+			.is_empty());
+	}
+}
@@ -0,0 +1,93 @@
+use oml_game::telemetry::TraceInfo;
+
+use crate::egui_telemetry_samples::EguiTraceSamples;
+
+/// All traces as they were in one frame, either live, frozen by pausing the widget, or captured by a trigger.
+#[derive(Debug, Default)]
+pub(crate) struct EguiTelemetrySnapshot {
+	pub traces_info: Vec<TraceInfo>,
+	pub samples:     Vec<EguiTraceSamples>,
+	/// Frames that already left the telemetry buffer when the snapshot was taken.
+	pub x_offset:    f64,
+}
+
+impl EguiTelemetrySnapshot {
+	pub fn capture() -> Self {
+		let traces_info = oml_game::DefaultTelemetry::traces_info();
+		let samples = traces_info.iter().map(EguiTraceSamples::load).collect();
+		let maximum_length = oml_game::DefaultTelemetry::maximum_length() as f64;
+		let frames = oml_game::DefaultTelemetry::frames() as f64;
+		Self {
+			traces_info,
+			samples,
+			x_offset: (frames - maximum_length).max(0.0),
+		}
+	}
+
+	/// Plot x of the first and the last frame in the snapshot.
+	pub fn frame_range(&self) -> std::ops::RangeInclusive<f64> {
+		let len = self.samples.iter().map(|s| s.ys.len()).max().unwrap_or(0);
+		self.x_offset..=self.x_offset + len.saturating_sub(1) as f64
+	}
+
+	/// Drops the samples of all frames outside `frames`.
+	pub fn retain_frames(&mut self, frames: std::ops::Range<u64>) {
+		let first_frame = self.x_offset as u64;
+		let start = frames.start.max(first_frame);
+		for samples in self.samples.iter_mut() {
+			let len = samples.ys.len() as u64;
+			let from = (start - first_frame).min(len) as usize;
+			let to = (frames.end.saturating_sub(first_frame).min(len) as usize).max(from);
+			samples.ys = samples.ys[from..to].to_vec();
+		}
+		self.x_offset = start as f64;
+	}
+
+	/// The sample of trace `index` at plot x `frame`, if there is one.
+	pub fn value_at(&self, index: usize, frame: f64) -> Option<f64> {
+		let i = frame - self.x_offset;
+		if i < 0.0 {
+			return None;
+		}
+		self.samples
+			.get(index)?
+			.ys
+			.get(i as usize)
+			.copied()
+			.flatten()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::egui_telemetry_samples::EguiTraceKind;
+
+	#[test]
+	fn value_at_is_offset_by_the_frames_that_left_the_buffer() {
+		let snapshot = EguiTelemetrySnapshot {
+			traces_info: Vec::new(),
+			samples:     vec![EguiTraceSamples {
+				kind: EguiTraceKind::Number,
+				ys:   vec![Some(1.0), None, Some(3.0)],
+			}],
+			x_offset:    100.0,
+		};
+
+		assert_eq!(snapshot.frame_range(), 100.0..=102.0);
+		assert_eq!(snapshot.value_at(0, 100.0), Some(1.0));
+		assert_eq!(snapshot.value_at(0, 101.0), None);
+		assert_eq!(snapshot.value_at(0, 102.4), Some(3.0));
+		// rolled out of the buffer, or not there yet
+		assert_eq!(snapshot.value_at(0, 99.0), None);
+		assert_eq!(snapshot.value_at(0, 103.0), None);
+		assert_eq!(snapshot.value_at(1, 100.0), None);
+	}
+
+	#[test]
+	fn empty_snapshot() {
+		let snapshot = EguiTelemetrySnapshot::default();
+		assert_eq!(snapshot.frame_range(), 0.0..=0.0);
+		assert_eq!(snapshot.value_at(0, 0.0), None);
+	}
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+/// Which samples the statistics cover.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EguiStatsScope {
+	/// The frames currently visible in the plot, the other views fall back to `Buffer`.
+	#[default]
+	Visible,
+	/// Everything still in the telemetry buffer.
+	Buffer,
+}
+
+/// Statistics over a sliding window of frames of one trace.
+///
+/// The window usually only moves forward, so frames are added and dropped one by one instead of rescanning the buffer.
+/// Jumping around, e.g. zooming out, rebuilds it.
+#[derive(Debug, Default)]
+pub(crate) struct EguiTraceStats {
+	frames:  Range<u64>,
+	values:  VecDeque<Option<f64>>,
+	sorted:  Vec<f64>,
+	sum:     f64,
+	sum_sq:  f64,
+	current: Option<f64>,
+}
+
+impl EguiTraceStats {
+	/// `ys[0]` is the sample of frame `first_frame`.
+	pub fn update(&mut self, ys: &[Option<f64>], first_frame: u64, frames: Range<u64>) {
+		let available = first_frame..first_frame + ys.len() as u64;
+		let start = frames.start.clamp(available.start, available.end);
+		let end = frames.end.clamp(start, available.end);
+
+		if start < self.frames.start || start > self.frames.end || end < self.frames.end {
+			self.reset(start);
+		}
+
+		while self.frames.start < start {
+			if let Some(Some(y)) = self.values.pop_front() {
+				self.remove(y);
+			}
+			self.frames.start += 1;
+		}
+		while self.frames.end < end {
+			let y = ys[(self.frames.end - first_frame) as usize];
+			if let Some(y) = y {
+				self.add(y);
+			}
+			self.values.push_back(y);
+			self.frames.end += 1;
+		}
+
+		self.current = self.values.iter().rev().flatten().next().copied();
+	}
+
+	fn reset(&mut self, start: u64) {
+		self.frames = start..start;
+		self.values.clear();
+		self.sorted.clear();
+		self.sum = 0.0;
+		self.sum_sq = 0.0;
+	}
+
+	fn add(&mut self, y: f64) {
+		let pos = self.sorted.partition_point(|v| *v < y);
+		self.sorted.insert(pos, y);
+		self.sum += y;
+		self.sum_sq += y * y;
+	}
+
+	fn remove(&mut self, y: f64) {
+		let pos = self.sorted.partition_point(|v| *v < y);
+		if pos < self.sorted.len() {
+			self.sorted.remove(pos);
+		}
+		self.sum -= y;
+		self.sum_sq -= y * y;
+	}
+
+	pub fn count(&self) -> usize {
+		self.sorted.len()
+	}
+
+	/// The latest sample in the window.
+	pub fn current(&self) -> Option<f64> {
+		self.current
+	}
+
+	pub fn min(&self) -> Option<f64> {
+		self.sorted.first().copied()
+	}
+
+	pub fn max(&self) -> Option<f64> {
+		self.sorted.last().copied()
+	}
+
+	pub fn mean(&self) -> Option<f64> {
+		if self.sorted.is_empty() {
+			None
+		} else {
+			Some(self.sum / self.sorted.len() as f64)
+		}
+	}
+
+	pub fn std_dev(&self) -> Option<f64> {
+		let mean = self.mean()?;
+		let n = self.sorted.len() as f64;
+		// the running sums drift a bit, so don't let that turn into a NaN
+		Some((self.sum_sq / n - mean * mean).max(0.0).sqrt())
+	}
+
+	/// Nearest rank percentile, `p` from 0.0 to 1.0.
+	pub fn percentile(&self, p: f64) -> Option<f64> {
+		if self.sorted.is_empty() {
+			return None;
+		}
+		let rank = (p * self.sorted.len() as f64).ceil() as usize;
+		Some(self.sorted[rank.clamp(1, self.sorted.len()) - 1])
+	}
+
+	/// All samples in the window, sorted ascending.
+	pub fn sorted(&self) -> &[f64] {
+		&self.sorted
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ys(values: &[f64]) -> Vec<Option<f64>> {
+		values.iter().map(|v| Some(*v)).collect()
+	}
+
+	/// The same window computed from scratch.
+	fn fresh(ys: &[Option<f64>], first_frame: u64, frames: Range<u64>) -> EguiTraceStats {
+		let mut stats = EguiTraceStats::default();
+		stats.update(ys, first_frame, frames);
+		stats
+	}
+
+	fn assert_same(a: &EguiTraceStats, b: &EguiTraceStats) {
+		assert_eq!(a.sorted(), b.sorted());
+		assert_eq!(a.current(), b.current());
+		let mean = |s: &EguiTraceStats| s.mean().unwrap_or(0.0);
+		assert!((mean(a) - mean(b)).abs() < 1e-9);
+	}
+
+	#[test]
+	fn empty_window() {
+		let stats = fresh(&[], 0, 0..10);
+		assert_eq!(stats.count(), 0);
+		assert_eq!(stats.current(), None);
+		assert_eq!(stats.min(), None);
+		assert_eq!(stats.mean(), None);
+		assert_eq!(stats.std_dev(), None);
+		assert_eq!(stats.percentile(0.5), None);
+	}
+
+	#[test]
+	fn statistics_of_the_window() {
+		let stats = fresh(&[Some(4.0), None, Some(2.0), Some(6.0), None], 0, 0..5);
+		assert_eq!(stats.count(), 3);
+		assert_eq!(stats.current(), Some(6.0));
+		assert_eq!(stats.min(), Some(2.0));
+		assert_eq!(stats.max(), Some(6.0));
+		assert_eq!(stats.mean(), Some(4.0));
+		assert!((stats.std_dev().unwrap() - (8.0f64 / 3.0).sqrt()).abs() < 1e-9);
+		assert_eq!(stats.percentile(0.0), Some(2.0));
+		assert_eq!(stats.percentile(0.5), Some(4.0));
+		assert_eq!(stats.percentile(1.0), Some(6.0));
+	}
+
+	#[test]
+	fn all_values_equal() {
+		let stats = fresh(&ys(&[0.1; 7]), 0, 0..7);
+		assert_eq!(stats.min(), stats.max());
+		assert_eq!(stats.percentile(0.9), Some(0.1));
+		assert!(!stats.std_dev().unwrap().is_nan());
+	}
+
+	#[test]
+	fn slides_with_a_ring_buffer_that_rolls_over() {
+		let all: Vec<Option<f64>> = (0..20).map(|i| Some(((i * 7) % 5) as f64)).collect();
+		let mut stats = EguiTraceStats::default();
+		// the buffer holds 8 frames, and the window shows the last 5 of them
+		for frame in 8..=20u64 {
+			let first_frame = frame - 8;
+			let buffer = &all[first_frame as usize..frame as usize];
+			stats.update(buffer, first_frame, frame - 5..frame);
+			assert_same(&stats, &fresh(buffer, first_frame, frame - 5..frame));
+			assert_eq!(stats.count(), 5);
+		}
+	}
+
+	#[test]
+	fn jumping_back_rebuilds() {
+		let buffer = ys(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+		let mut stats = EguiTraceStats::default();
+		stats.update(&buffer, 0, 3..6);
+		stats.update(&buffer, 0, 0..2);
+		assert_same(&stats, &fresh(&buffer, 0, 0..2));
+
+		// zooming out
+		stats.update(&buffer, 0, 0..6);
+		assert_same(&stats, &fresh(&buffer, 0, 0..6));
+	}
+
+	#[test]
+	fn clamps_the_window_to_the_buffer() {
+		let buffer = ys(&[1.0, 2.0, 3.0]);
+		let stats = fresh(&buffer, 10, 0..100);
+		assert_eq!(stats.sorted(), &[1.0, 2.0, 3.0]);
+
+		// entirely outside
+		assert_eq!(fresh(&buffer, 10, 0..5).count(), 0);
+		assert_eq!(fresh(&buffer, 10, 20..30).count(), 0);
+	}
+}
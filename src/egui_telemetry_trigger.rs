@@ -0,0 +1,350 @@
+use std::ops::Range;
+use std::time::SystemTime;
+
+use crate::egui_telemetry_snapshot::EguiTelemetrySnapshot;
+
+/// When a trigger fires.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum EguiTriggerCondition {
+	#[default]
+	Above,
+	Below,
+}
+
+impl EguiTriggerCondition {
+	pub fn is_met(&self, value: f64, level: f64) -> bool {
+		match self {
+			EguiTriggerCondition::Above => value > level,
+			EguiTriggerCondition::Below => value < level,
+		}
+	}
+}
+
+/// Captures the frames around a spike, like the trigger of an oscilloscope.
+///
+/// ```ignore
+/// // keep the second around every frame that took longer than 50ms
+/// telemetry.arm_trigger(
+///     EguiTrigger::new("time_step", EguiTriggerCondition::Above, 0.05)
+///         .with_pre_frames(60)
+///         .with_post_frames(60),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct EguiTrigger {
+	trace_id:    String,
+	condition:   EguiTriggerCondition,
+	level:       f64,
+	pre_frames:  usize,
+	post_frames: usize,
+	rearm:       bool,
+}
+
+impl Default for EguiTrigger {
+	fn default() -> Self {
+		Self {
+			trace_id:    String::new(),
+			condition:   EguiTriggerCondition::Above,
+			level:       0.0,
+			pre_frames:  60,
+			post_frames: 60,
+			rearm:       true,
+		}
+	}
+}
+
+impl EguiTrigger {
+	pub fn new(trace_id: &str, condition: EguiTriggerCondition, level: f64) -> Self {
+		Self {
+			trace_id: trace_id.to_string(),
+			condition,
+			level,
+			..Default::default()
+		}
+	}
+
+	/// Frames kept before the one that fired, limited by what's still in the telemetry buffer.
+	pub fn with_pre_frames(mut self, pre_frames: usize) -> Self {
+		self.pre_frames = pre_frames;
+		self
+	}
+
+	/// Frames kept after the one that fired, the capture is saved once they are in.
+	pub fn with_post_frames(mut self, post_frames: usize) -> Self {
+		self.post_frames = post_frames;
+		self
+	}
+
+	/// Stays armed after a capture, instead of firing just once.
+	pub fn with_rearm(mut self, rearm: bool) -> Self {
+		self.rearm = rearm;
+		self
+	}
+
+	pub fn trace_id(&self) -> &str {
+		&self.trace_id
+	}
+
+	pub fn condition(&self) -> EguiTriggerCondition {
+		self.condition
+	}
+
+	pub fn level(&self) -> f64 {
+		self.level
+	}
+
+	pub fn pre_frames(&self) -> usize {
+		self.pre_frames
+	}
+
+	pub fn post_frames(&self) -> usize {
+		self.post_frames
+	}
+
+	pub fn rearm(&self) -> bool {
+		self.rearm
+	}
+}
+
+/// The frames around one firing of a trigger.
+#[derive(Debug)]
+pub(crate) struct EguiTelemetryCapture {
+	pub timestamp:     SystemTime,
+	pub trace_name:    String,
+	pub trigger_frame: u64,
+	pub value:         f64,
+	pub snapshot:      EguiTelemetrySnapshot,
+}
+
+impl EguiTelemetryCapture {
+	/// Keeps the frames of `firing` from `live`, the snapshot it was completed in.
+	pub fn new(firing: EguiTriggerFiring, mut live: EguiTelemetrySnapshot) -> Self {
+		live.retain_frames(firing.frames);
+		Self {
+			timestamp:     firing.timestamp,
+			trace_name:    firing.trace_name,
+			trigger_frame: firing.trigger_frame,
+			value:         firing.value,
+			snapshot:      live,
+		}
+	}
+
+	/// Wall clock time of day in UTC, plus what fired.
+	pub fn label(&self) -> String {
+		let secs = self
+			.timestamp
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.map(|d| d.as_secs())
+			.unwrap_or(0);
+		format!(
+			"{:02}:{:02}:{:02} frame {} {} = {:.4}",
+			(secs / 3600) % 24,
+			(secs / 60) % 60,
+			secs % 60,
+			self.trigger_frame,
+			self.trace_name,
+			self.value
+		)
+	}
+}
+
+/// A trigger that fired, once all the frames after it are in.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EguiTriggerFiring {
+	pub timestamp:     SystemTime,
+	pub trace_name:    String,
+	pub trigger_frame: u64,
+	pub value:         f64,
+	/// The frames to capture, limited to the ones still in the buffer.
+	pub frames:        Range<u64>,
+}
+
+/// Watches the live telemetry for an `EguiTrigger`.
+#[derive(Debug, Default)]
+pub(crate) struct EguiTriggerState {
+	next_frame: u64,
+	/// Frame, value and time of the firing, waiting for the frames after it.
+	pending:    Option<(u64, f64, SystemTime)>,
+}
+
+impl EguiTriggerState {
+	/// Forgets a pending capture, and ignores everything before `end_frame`.
+	pub fn reset(&mut self, end_frame: u64) {
+		self.pending = None;
+		self.next_frame = end_frame;
+	}
+
+	/// Checks the frames that arrived since the last poll, and returns the firing once its frames are complete.
+	pub fn poll(
+		&mut self,
+		trigger: &EguiTrigger,
+		live: &EguiTelemetrySnapshot,
+	) -> Option<EguiTriggerFiring> {
+		let index = live
+			.traces_info
+			.iter()
+			.position(|ti| ti.id() == trigger.trace_id)?;
+		let ys = &live.samples[index].ys;
+		let first_frame = live.x_offset as u64;
+		let end_frame = first_frame + ys.len() as u64;
+
+		if self.pending.is_none() {
+			for frame in self.next_frame.max(first_frame)..end_frame {
+				if let Some(y) = ys[(frame - first_frame) as usize] {
+					if trigger.condition.is_met(y, trigger.level) {
+						self.pending = Some((frame, y, SystemTime::now()));
+						break;
+					}
+				}
+			}
+			self.next_frame = self.next_frame.max(end_frame);
+		}
+
+		let (frame, value, timestamp) = self.pending?;
+		let capture_end = frame + trigger.post_frames as u64 + 1;
+		if end_frame < capture_end {
+			return None;
+		}
+		self.pending = None;
+		// no retriggering on the frames that are already in this capture
+		self.next_frame = capture_end;
+
+		let capture_start = frame
+			.saturating_sub(trigger.pre_frames as u64)
+			.max(first_frame);
+
+		Some(EguiTriggerFiring {
+			timestamp,
+			trace_name: live.traces_info[index].name().to_string(),
+			trigger_frame: frame,
+			value,
+			frames: capture_start..capture_end,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use oml_game::telemetry::TraceInfo;
+
+	use super::*;
+	use crate::egui_telemetry_samples::EguiTraceKind;
+	use crate::egui_telemetry_samples::EguiTraceSamples;
+
+	/// One trace with the default, empty id, holding `ys` from `first_frame` on.
+	fn snapshot(ys: &[Option<f64>], first_frame: u64) -> EguiTelemetrySnapshot {
+		EguiTelemetrySnapshot {
+			traces_info: vec![TraceInfo::default()],
+			samples:     vec![EguiTraceSamples {
+				kind: EguiTraceKind::Number,
+				ys:   ys.to_vec(),
+			}],
+			x_offset:    first_frame as f64,
+		}
+	}
+
+	/// A ring buffer of `len` frames over `all`, after `frames` frames were traced.
+	fn buffer(all: &[Option<f64>], frames: usize, len: usize) -> EguiTelemetrySnapshot {
+		let first_frame = frames.saturating_sub(len);
+		snapshot(&all[first_frame..frames], first_frame as u64)
+	}
+
+	fn spikes_at(spikes: &[usize], len: usize) -> Vec<Option<f64>> {
+		(0..len)
+			.map(|i| Some(if spikes.contains(&i) { 2.0 } else { 0.0 }))
+			.collect()
+	}
+
+	fn trigger(pre_frames: usize, post_frames: usize) -> EguiTrigger {
+		EguiTrigger::new("", EguiTriggerCondition::Above, 1.0)
+			.with_pre_frames(pre_frames)
+			.with_post_frames(post_frames)
+	}
+
+	#[test]
+	fn fires_once_the_post_frames_are_in() {
+		let all = spikes_at(&[10], 30);
+		let trigger = trigger(3, 2);
+		let mut state = EguiTriggerState::default();
+
+		for frames in 1..=12 {
+			assert_eq!(state.poll(&trigger, &buffer(&all, frames, 20)), None);
+		}
+		let firing = state.poll(&trigger, &buffer(&all, 13, 20)).unwrap();
+		assert_eq!(firing.trigger_frame, 10);
+		assert_eq!(firing.value, 2.0);
+		assert_eq!(firing.frames, 7..13);
+
+		for frames in 14..30 {
+			assert_eq!(state.poll(&trigger, &buffer(&all, frames, 20)), None);
+		}
+	}
+
+	#[test]
+	fn pre_frames_outside_the_buffer_are_dropped() {
+		let all = spikes_at(&[12], 20);
+		// the buffer only holds 6 frames, so 12 - 10 already left it
+		let firing = EguiTriggerState::default()
+			.poll(&trigger(10, 1), &buffer(&all, 14, 6))
+			.unwrap();
+		assert_eq!(firing.frames, 8..14);
+
+		let mut live = buffer(&all, 14, 6);
+		live.retain_frames(firing.frames);
+		assert_eq!(live.x_offset, 8.0);
+		assert_eq!(live.samples[0].ys.len(), 6);
+		assert_eq!(live.value_at(0, 12.0), Some(2.0));
+	}
+
+	#[test]
+	fn ignores_frames_from_before_arming() {
+		let all = spikes_at(&[3, 8], 20);
+		let trigger = trigger(0, 0);
+		let mut state = EguiTriggerState::default();
+		state.reset(5);
+
+		let firing = state.poll(&trigger, &buffer(&all, 10, 20)).unwrap();
+		assert_eq!(firing.trigger_frame, 8);
+	}
+
+	#[test]
+	fn does_not_retrigger_inside_a_capture() {
+		// 11 and 12 are part of the capture of 10, 20 is a new spike
+		let all = spikes_at(&[10, 11, 12, 20], 30);
+		let trigger = trigger(0, 2);
+		let mut state = EguiTriggerState::default();
+
+		let mut fired = Vec::new();
+		// polled every 4 frames, e.g. while the game hitches
+		for frames in (4..=30).step_by(4) {
+			if let Some(firing) = state.poll(&trigger, &buffer(&all, frames, 8)) {
+				fired.push(firing.trigger_frame);
+			}
+		}
+		assert_eq!(fired, vec![10, 20]);
+	}
+
+	#[test]
+	fn rolled_over_buffers_and_missing_traces() {
+		let mut state = EguiTriggerState::default();
+		let trigger = trigger(0, 0);
+		assert_eq!(
+			state.poll(&trigger, &EguiTelemetrySnapshot::default()),
+			None
+		);
+		assert_eq!(
+			state.poll(
+				&EguiTrigger::new("other", EguiTriggerCondition::Above, 1.0),
+				&snapshot(&[Some(2.0)], 0)
+			),
+			None
+		);
+
+		// all of frames 0..100 left a buffer of 4 between two polls
+		let all = spikes_at(&[50, 101], 110);
+		assert_eq!(state.poll(&trigger, &buffer(&all, 4, 4)), None);
+		let firing = state.poll(&trigger, &buffer(&all, 104, 4)).unwrap();
+		assert_eq!(firing.trigger_frame, 101);
+		assert_eq!(firing.frames, 101..102);
+	}
+}
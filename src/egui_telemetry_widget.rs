@@ -1,32 +1,120 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::ops::Range;
 
-use egui::epaint::{emath::lerp, vec2, Color32, Pos2, Shape, Stroke};
-use egui::plot::{Line, Plot, PlotPoints};
-use egui::WidgetWithState;
-use egui::{Response, Sense, Ui, Widget};
+use egui::epaint::{vec2, Color32, Stroke};
+use egui::plot::{
+	Bar, BarChart, BoxElem, BoxPlot, BoxSpread, HLine, Legend, Line, LinkedAxisGroup,
+	LinkedCursorsGroup, MarkerShape, Plot, PlotBounds, PlotPoint, PlotPoints, PlotUi, Points,
+	VLine,
+};
+use egui::{Sense, Ui};
+use oml_game::system::System;
 use oml_game::telemetry::TraceInfo;
+use serde::Deserialize;
+use serde::Serialize;
 
-#[derive(Debug)]
+use crate::egui_telemetry_budget;
+use crate::egui_telemetry_budget::EguiBudgetCounter;
+use crate::egui_telemetry_config::EguiTelemetryConfig;
+use crate::egui_telemetry_distribution;
+use crate::egui_telemetry_distribution::EguiTelemetryView;
+use crate::egui_telemetry_samples::EguiTraceKind;
+use crate::egui_telemetry_samples::EguiTraceSamples;
+use crate::egui_telemetry_snapshot::EguiTelemetrySnapshot;
+use crate::egui_telemetry_stats::EguiStatsScope;
+use crate::egui_telemetry_stats::EguiTraceStats;
+use crate::egui_telemetry_trigger::EguiTelemetryCapture;
+use crate::egui_telemetry_trigger::EguiTrigger;
+use crate::egui_telemetry_trigger::EguiTriggerCondition;
+use crate::egui_telemetry_trigger::EguiTriggerState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TraceConfig {
-	pub enabled: bool,
-	pub color:   egui::Color32,
+	pub enabled:    bool,
+	pub color:      egui::Color32,
+	/// Drawn as a horizontal line at its latest value, for other traces to be checked against.
+	pub threshold:  bool,
+	/// Id of the threshold trace this one has to stay under.
+	pub budget:     Option<String>,
+	/// Shades the frames where the trace wasn't written.
+	pub shade_gaps: bool,
+	/// Which of the stacked plots the trace goes into, each has its own Y axis.
+	pub subplot:    usize,
 }
 
 impl Default for TraceConfig {
 	fn default() -> Self {
 		Self {
-			enabled: true,
-			color:   egui::Color32::GOLD, //TRANSPARENT,
+			enabled:    true,
+			color:      egui::Color32::GOLD, //TRANSPARENT,
+			threshold:  false,
+			budget:     None,
+			shade_gaps: false,
+			subplot:    0,
+		}
+	}
+}
+
+const DEFAULT_HISTOGRAM_BINS: usize = 32;
+const MAX_SUBPLOTS: usize = 8;
+/// Every capture holds all traces, so only the latest ones are kept.
+const MAX_CAPTURES: usize = 16;
+
+/// Links the X axis and cursor of the stacked plots, has to live as long as the plots.
+#[derive(Clone)]
+struct EguiPlotLinks {
+	axis:    LinkedAxisGroup,
+	cursors: LinkedCursorsGroup,
+}
+
+impl Default for EguiPlotLinks {
+	fn default() -> Self {
+		Self {
+			axis:    LinkedAxisGroup::new(true, false),
+			cursors: LinkedCursorsGroup::new(true, false),
 		}
 	}
 }
 
+impl std::fmt::Debug for EguiPlotLinks {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("EguiPlotLinks").finish_non_exhaustive()
+	}
+}
+
 #[derive(Debug, Default)]
 pub struct EguiTelemetryWidget {
-	count:               usize,
-	size:                Option<f32>,
-	trace_configs:       HashMap<String, TraceConfig>,
-	next_auto_color_idx: usize,
+	count:            usize,
+	size:             Option<f32>,
+	trace_configs:    HashMap<String, TraceConfig>,
+	presets:          BTreeMap<String, BTreeMap<String, TraceConfig>>,
+	active_preset:    Option<String>,
+	new_preset_name:  String,
+	show_stats:       bool,
+	stats_scope:      EguiStatsScope,
+	stats:            HashMap<String, EguiTraceStats>,
+	visible_frames:   Option<Range<f64>>,
+	view:             EguiTelemetryView,
+	histogram_bins:   Option<usize>,
+	cdf_reference:    Option<String>,
+	budget_counters:  HashMap<String, EguiBudgetCounter>,
+	shade_violations: bool,
+	snapshot:         Option<EguiTelemetrySnapshot>,
+	x_offset:         f64,
+	paused:           bool,
+	follow_latest:    bool,
+	scrub_frame:      Option<f64>,
+	trigger:          EguiTrigger,
+	trigger_armed:    bool,
+	trigger_state:    EguiTriggerState,
+	captures:         Vec<EguiTelemetryCapture>,
+	viewing_capture:  Option<usize>,
+	clear_captures:   bool,
+	plot_links:       EguiPlotLinks,
+	live:             Option<EguiTelemetrySnapshot>,
+	updated_frames:   Option<usize>,
 }
 
 impl EguiTelemetryWidget {
@@ -34,11 +122,36 @@ impl EguiTelemetryWidget {
 		let size = self
 			.size
 			.unwrap_or_else(|| ui.style().spacing.interact_size.y);
-		let (rect, response) = ui.allocate_exact_size(vec2(size, size), Sense::hover());
+		let (rect, _) = ui.allocate_exact_size(vec2(size, size), Sense::hover());
 
 		self.count += 1;
 		if ui.is_rect_visible(rect) {
-			let traces_info = oml_game::DefaultTelemetry::traces_info();
+			self.update();
+			let viewing_capture = self.viewing_capture.filter(|i| *i < self.captures.len());
+			// a paused widget keeps looking at the frame it was paused in
+			let snapshot = match viewing_capture {
+				// viewing a capture leaves the paused snapshot alone
+				Some(i) => std::mem::take(&mut self.captures[i].snapshot),
+				None => {
+					let paused = if self.paused {
+						self.snapshot.take()
+					} else {
+						None
+					};
+					paused
+						.or_else(|| self.live.take())
+						.unwrap_or_else(EguiTelemetrySnapshot::capture)
+				},
+			};
+			self.x_offset = snapshot.x_offset;
+			let traces_info = &snapshot.traces_info;
+			let samples = &snapshot.samples;
+			let violations = self.budget_violations(traces_info, samples);
+			let thresholds: Vec<(String, String)> = traces_info
+				.iter()
+				.filter(|ti| self.trace_config(ti.id()).threshold)
+				.map(|ti| (ti.id().to_string(), ti.name().to_string()))
+				.collect();
 			egui::SidePanel::left("traces_panel")
 				.resizable(true)
 				.default_width(150.0)
@@ -48,20 +161,28 @@ impl EguiTelemetryWidget {
 						ui.heading("Traces");
 					});
 					egui::ScrollArea::vertical().show(ui, |ui| {
-						for ti in traces_info.iter() {
-							let color = self.next_auto_color();
+						for (ti, samples) in traces_info.iter().zip(samples.iter()) {
 							ui.group(|ui| {
-								let tc = self
-									.trace_configs
-									.entry(ti.id().to_string())
-									//.or_default();
-									.or_insert(TraceConfig {
-										color, //: self.next_auto_color(),
-										..Default::default()
-									});
-								//let tc =
-								//	self.trace_configs.entry("hoax".to_string()).or_default();
-								ui.checkbox(&mut tc.enabled, ti.name());
+								let tc = self.trace_config(ti.id());
+								match samples.kind {
+									EguiTraceKind::Number => {
+										EguiTelemetryWidget::trace_checkbox(ui, tc, ti.name());
+										EguiTelemetryWidget::trace_budget(
+											ui,
+											tc,
+											ti.id(),
+											&thresholds,
+										);
+										if let Some(counter) = self.budget_counters.get(ti.id()) {
+											ui.label(format!("{} over budget", counter.total()));
+										}
+									},
+									EguiTraceKind::Unsupported => {
+										ui.label(format!("⚠ {}", ti.name())).on_hover_text(
+											"No samples of a type the widget can plot",
+										);
+									},
+								}
 								//ui.label(ti.name());
 								//ui.set_min_height(20.0); // :HACK:
 								ui.end_row();
@@ -74,90 +195,899 @@ impl EguiTelemetryWidget {
 			egui::TopBottomPanel::bottom("bottom_panel")
 				.resizable(false)
 				.min_height(0.0)
-				.show_inside(ui, |ui| {});
+				.show_inside(ui, |ui| {
+					ui.horizontal(|ui| {
+						self.show_presets(ui);
+						ui.separator();
+						ui.toggle_value(&mut self.show_stats, "Statistics");
+						ui.toggle_value(&mut self.shade_violations, "Shade over budget");
+						// only the lines have visible frames
+						if self.show_stats && self.view == EguiTelemetryView::Lines {
+							ui.selectable_value(
+								&mut self.stats_scope,
+								EguiStatsScope::Visible,
+								"Visible",
+							);
+							ui.selectable_value(
+								&mut self.stats_scope,
+								EguiStatsScope::Buffer,
+								"Buffer",
+							);
+						}
+					});
+					ui.horizontal(|ui| {
+						self.show_view_selection(ui, traces_info);
+					});
+					self.show_time_controls(ui, &snapshot);
+					self.show_trigger_controls(ui, traces_info);
+				});
+
+			if self.show_stats || self.view != EguiTelemetryView::Lines {
+				self.update_stats(traces_info, samples);
+			}
+
+			if self.show_stats {
+				egui::TopBottomPanel::bottom("stats_panel")
+					.resizable(true)
+					.show_inside(ui, |ui| {
+						self.show_stats_table(ui, traces_info, samples);
+					});
+			}
+
+			egui::CentralPanel::default().show_inside(ui, |ui| match self.view {
+				EguiTelemetryView::Lines => self.show_lines(ui, traces_info, samples, &violations),
+				view => self.show_distribution(ui, traces_info, samples, view),
+			});
+
+			match viewing_capture {
+				Some(i) => self.captures[i].snapshot = snapshot,
+				None if self.paused => self.snapshot = Some(snapshot),
+				None => {},
+			}
+			if self.clear_captures {
+				self.clear_captures = false;
+				self.captures.clear();
+				self.viewing_capture = None;
+			}
+		}
+	}
+
+	/// Watches the live telemetry, call once per frame even while the widget isn't shown,
+	/// so no spike is missed and the budget counts stay complete.
+	pub fn update(&mut self) {
+		let frames = oml_game::DefaultTelemetry::frames();
+		if self.updated_frames == Some(frames) {
+			return;
+		}
+		self.updated_frames = Some(frames);
+
+		let live = EguiTelemetrySnapshot::capture();
+		// frames leaving the buffer while paused still have to be counted
+		self.update_budgets(&live);
+		if self.trigger_armed {
+			self.live = self.poll_trigger(live);
+		} else {
+			self.live = Some(live);
+		}
+	}
+
+	/// Starts watching the live telemetry for `trigger`, replacing the previous one.
+	pub fn arm_trigger(&mut self, trigger: EguiTrigger) {
+		self.trigger = trigger;
+		self.trigger_armed = true;
+		self.reset_trigger();
+	}
+
+	pub fn disarm_trigger(&mut self) {
+		self.trigger_armed = false;
+		self.reset_trigger();
+	}
+
+	/// Only frames traced from now on can fire the trigger.
+	fn reset_trigger(&mut self) {
+		self.trigger_state
+			.reset(oml_game::DefaultTelemetry::frames() as u64);
+	}
+
+	pub fn is_trigger_armed(&self) -> bool {
+		self.trigger_armed
+	}
+
+	/// Number of captures the trigger took so far.
+	pub fn capture_count(&self) -> usize {
+		self.captures.len()
+	}
+
+	/// Hands `live` back unless a capture took it.
+	fn poll_trigger(&mut self, live: EguiTelemetrySnapshot) -> Option<EguiTelemetrySnapshot> {
+		match self.trigger_state.poll(&self.trigger, &live) {
+			Some(firing) => {
+				self.add_capture(EguiTelemetryCapture::new(firing, live));
+				None
+			},
+			None => Some(live),
+		}
+	}
 
-			egui::CentralPanel::default().show_inside(ui, |ui| {
-				Plot::new("time_step").show(ui, |plot_ui| {
+	fn add_capture(&mut self, capture: EguiTelemetryCapture) {
+		tracing::info!("Telemetry trigger fired: {}", capture.label());
+		if self.captures.len() >= MAX_CAPTURES {
+			self.captures.remove(0);
+			self.viewing_capture = match self.viewing_capture {
+				Some(0) | None => None,
+				Some(i) => Some(i - 1),
+			};
+		}
+		self.captures.push(capture);
+		if !self.trigger.rearm() {
+			self.trigger_armed = false;
+		}
+	}
+
+	fn show_trigger_controls(&mut self, ui: &mut Ui, traces_info: &[TraceInfo]) {
+		ui.horizontal(|ui| {
+			if ui
+				.toggle_value(&mut self.trigger_armed, "Trigger")
+				.changed()
+			{
+				self.reset_trigger();
+			}
+
+			let selected = traces_info
+				.iter()
+				.find(|ti| ti.id() == self.trigger.trace_id())
+				.map(|ti| ti.name().to_string())
+				.unwrap_or_else(|| "Trace".to_string());
+			let mut trace_id = self.trigger.trace_id().to_string();
+			egui::ComboBox::from_id_source("trigger_trace")
+				.selected_text(selected)
+				.show_ui(ui, |ui| {
 					for ti in traces_info.iter() {
-						let tc = self.trace_configs.entry(ti.id().to_string()).or_default();
-						if tc.enabled {
-							let color = tc.color;
-							let mut line = self.lines_from_trace_info(ti);
-							line = line.color(color);
-							plot_ui.line(line);
-						}
+						ui.selectable_value(&mut trace_id, ti.id().to_string(), ti.name());
+					}
+				});
+
+			let mut condition = self.trigger.condition();
+			ui.selectable_value(&mut condition, EguiTriggerCondition::Above, ">");
+			ui.selectable_value(&mut condition, EguiTriggerCondition::Below, "<");
+			let mut level = self.trigger.level();
+			ui.add(egui::DragValue::new(&mut level).speed(0.001));
+			let mut pre_frames = self.trigger.pre_frames();
+			ui.add(egui::DragValue::new(&mut pre_frames).prefix("Before: "));
+			let mut post_frames = self.trigger.post_frames();
+			ui.add(egui::DragValue::new(&mut post_frames).prefix("After: "));
+
+			if trace_id != self.trigger.trace_id()
+				|| condition != self.trigger.condition()
+				|| level != self.trigger.level()
+				|| pre_frames != self.trigger.pre_frames()
+				|| post_frames != self.trigger.post_frames()
+			{
+				self.trigger = EguiTrigger::new(&trace_id, condition, level)
+					.with_pre_frames(pre_frames)
+					.with_post_frames(post_frames)
+					.with_rearm(self.trigger.rearm());
+				self.reset_trigger();
+			}
+
+			ui.separator();
+			let selected = match self.viewing_capture.and_then(|i| self.captures.get(i)) {
+				Some(capture) => capture.label(),
+				None => format!("Live ({} captures)", self.captures.len()),
+			};
+			egui::ComboBox::from_id_source("telemetry_captures")
+				.selected_text(selected)
+				.show_ui(ui, |ui| {
+					ui.selectable_value(&mut self.viewing_capture, None, "Live");
+					for (i, capture) in self.captures.iter().enumerate() {
+						ui.selectable_value(&mut self.viewing_capture, Some(i), capture.label());
 					}
 				});
+			if !self.captures.is_empty() && ui.button("Clear").clicked() {
+				self.clear_captures = true;
+			}
+		});
+	}
+
+	fn show_time_controls(&mut self, ui: &mut Ui, snapshot: &EguiTelemetrySnapshot) {
+		let frames = snapshot.frame_range();
+		ui.horizontal(|ui| {
+			if ui.toggle_value(&mut self.paused, "⏸ Pause").changed() {
+				self.scrub_frame = if self.paused {
+					Some(*frames.end())
+				} else {
+					None
+				};
+			}
+			ui.toggle_value(&mut self.follow_latest, "Follow latest");
+			if let Some(scrub_frame) = &mut self.scrub_frame {
+				ui.add(
+					egui::Slider::new(scrub_frame, frames)
+						.step_by(1.0)
+						.text("Frame"),
+				);
+			}
+		});
+
+		if let Some(scrub_frame) = self.scrub_frame {
+			ui.horizontal_wrapped(|ui| {
+				for (i, ti) in snapshot.traces_info.iter().enumerate() {
+					let tc = match self.trace_configs.get(ti.id()) {
+						Some(tc) if tc.enabled => tc,
+						_ => continue,
+					};
+					let value = match snapshot.value_at(i, scrub_frame) {
+						Some(v) => format!("{:.4}", v),
+						None => "-".to_string(),
+					};
+					ui.colored_label(tc.color, format!("{}: {}", ti.name(), value));
+				}
 			});
 		}
 	}
-	/*
-		fn ys_from_trace<T>(&self, name: &str) -> Vec<Option<f32>>
-		where T: oml_game::telemetry::TelemetryEntry,
-		{
-			let v = oml_game::DefaultTelemetry::get::<T>(name);
-			//let u: u8 = v.iter();
-			//v.iter().flatten().map(|f| Some(*f as f32) ).collect()
-			v.iter().map(|mt| mt.as_ref().map(|t| *t as f32)).collect()
-		}
-	*/
-	fn ys_from_trace_f32(&self, name: &str) -> Vec<Option<f32>> {
-		let v = oml_game::DefaultTelemetry::get::<f32>(name);
-		//let u: u8 = v.iter();
-		//v.iter().flatten().map(|f| Some(*f as f32) ).collect()
-		v.iter().map(|mt| mt.as_ref().map(|t| *t as f32)).collect()
-	}
-
-	fn ys_from_trace_f64(&self, name: &str) -> Vec<Option<f32>> {
-		let v = oml_game::DefaultTelemetry::get::<f64>(name);
-		//let u: u8 = v.iter();
-		//v.iter().flatten().map(|f| Some(*f as f32) ).collect()
-		v.iter().map(|mt| mt.as_ref().map(|t| *t as f32)).collect()
-	}
-
-	fn ys_from_trace(&self, name: &str) -> Vec<Option<f32>> {
-		let ys_f32 = self.ys_from_trace_f32(name);
-		if !ys_f32.is_empty() {
-			return ys_f32;
-		}
-		let ys_f64 = self.ys_from_trace_f64(name);
-		if !ys_f64.is_empty() {
-			return ys_f64;
-		}
-
-		Vec::new()
-	}
-
-	fn lines_from_trace_info(&self, trace_info: &TraceInfo) -> Line {
-		// :TODO: verify type
-		//let v = oml_game::DefaultTelemetry::get::<f32>(trace_info.name());
-		//tracing::debug!("lines_from_trace_info: {:#?}", v);
-		let maximum_length = oml_game::DefaultTelemetry::maximum_length() as f64;
-		let frames = oml_game::DefaultTelemetry::frames() as f64;
-		let gone_frames = (frames - maximum_length).max(0.0);
-		let x_offset = gone_frames;
-		let ys = self.ys_from_trace(trace_info.name());
-		//tracing::debug!("{} -> {}", trace_info.name(), ys.len());
-		let points: PlotPoints = ys
+
+	fn show_lines(
+		&mut self,
+		ui: &mut Ui,
+		traces_info: &[TraceInfo],
+		samples: &[EguiTraceSamples],
+		violations: &[Vec<usize>],
+	) {
+		let x_offset = self.x_offset;
+
+		let mut subplots: Vec<usize> = traces_info
+			.iter()
+			.filter_map(|ti| self.trace_configs.get(ti.id()))
+			.filter(|tc| tc.enabled)
+			.map(|tc| tc.subplot)
+			.collect();
+		subplots.sort_unstable();
+		subplots.dedup();
+		if subplots.is_empty() {
+			subplots.push(0);
+		}
+
+		let spacing = ui.spacing().item_spacing.y;
+		let height =
+			(ui.available_height() - spacing * (subplots.len() - 1) as f32) / subplots.len() as f32;
+		for subplot in subplots {
+			self.show_lines_plot(
+				ui,
+				subplot,
+				height,
+				traces_info,
+				samples,
+				violations,
+				x_offset,
+			);
+		}
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn show_lines_plot(
+		&mut self,
+		ui: &mut Ui,
+		subplot: usize,
+		height: f32,
+		traces_info: &[TraceInfo],
+		samples: &[EguiTraceSamples],
+		violations: &[Vec<usize>],
+		x_offset: f64,
+	) {
+		// thresholds also show up next to the traces that are checked against them
+		let budget_thresholds: Vec<String> = traces_info
 			.iter()
-			.enumerate()
-			.filter_map(|(i, f)| {
-				if let Some(f) = f {
-					Some([i as f64 + x_offset, *f as f64])
+			.filter_map(|ti| self.trace_configs.get(ti.id()))
+			.filter(|tc| tc.enabled && tc.subplot == subplot)
+			.filter_map(|tc| tc.budget.clone())
+			.collect();
+
+		Plot::new(("time_step", subplot))
+			.height(height)
+			.link_axis(self.plot_links.axis.clone())
+			.link_cursor(self.plot_links.cursors.clone())
+			.legend(Legend::default())
+			.label_formatter(|name, value| {
+				if name.is_empty() {
+					format!("frame {:.0}\n{:.4}", value.x, value.y)
 				} else {
-					None
+					format!("{}\nframe {:.0}\n{:.4}", name, value.x, value.y)
+				}
+			})
+			.show(ui, |plot_ui| {
+				let bounds = plot_ui.plot_bounds();
+				self.visible_frames = Some(bounds.min()[0]..bounds.max()[0]);
+
+				if plot_ui.pointer_coordinate_drag_delta() != egui::Vec2::ZERO {
+					// the user took over
+					self.follow_latest = false;
+				}
+				if self.follow_latest && !self.paused {
+					let last = samples.iter().map(|s| s.ys.len()).max().unwrap_or(0) as f64;
+					let x_max = x_offset + last;
+					plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+						[x_max - bounds.width(), bounds.min()[1]],
+						[x_max, bounds.max()[1]],
+					));
+				}
+				if let Some(scrub_frame) = self.scrub_frame {
+					plot_ui.vline(VLine::new(scrub_frame).color(Color32::WHITE));
+				}
+
+				let hovered = match plot_ui.pointer_coordinate() {
+					Some(pointer) => {
+						let max_distance = 0.05 * plot_ui.plot_bounds().height();
+						self.hovered_trace(
+							traces_info,
+							samples,
+							subplot,
+							x_offset,
+							pointer,
+							max_distance,
+						)
+					},
+					None => None,
+				};
+				for (i, (ti, samples)) in traces_info.iter().zip(samples.iter()).enumerate() {
+					let tc = self.trace_config(ti.id());
+					if tc.enabled
+						&& tc.threshold && (tc.subplot == subplot
+						|| budget_thresholds.iter().any(|b| b == ti.id()))
+					{
+						if let Some(limit) = samples.ys.iter().rev().flatten().next() {
+							plot_ui.hline(HLine::new(*limit).color(tc.color).name(ti.name()));
+						}
+						continue;
+					}
+					if tc.subplot != subplot {
+						continue;
+					}
+					if tc.enabled && samples.kind != EguiTraceKind::Unsupported {
+						let color = tc.color;
+						// one line per run of samples, all with the same name so they share a legend entry
+						for segment in samples.segments(x_offset) {
+							if segment.len() == 1 {
+								// a line needs two points, a lonely sample would vanish
+								plot_ui.points(
+									Points::new(segment)
+										.shape(MarkerShape::Circle)
+										.radius(2.0)
+										.color(color)
+										.name(ti.name())
+										.highlight(hovered == Some(i)),
+								);
+								continue;
+							}
+							let points: PlotPoints = segment.into();
+							plot_ui.line(
+								Line::new(points)
+									.color(color)
+									.name(ti.name())
+									.highlight(hovered == Some(i)),
+							);
+						}
+
+						if tc.shade_gaps {
+							for gap in samples.gaps() {
+								EguiTelemetryWidget::shade_frames(
+									plot_ui,
+									gap.start as f64 + x_offset..gap.end as f64 + x_offset,
+									color.linear_multiply(0.1),
+									ti.name(),
+								);
+							}
+						}
+
+						if let Some(violations) = violations.get(i).filter(|v| !v.is_empty()) {
+							let name = format!("{} over budget", ti.name());
+							let points: PlotPoints = violations
+								.iter()
+								.filter_map(|v| samples.ys[*v].map(|y| [*v as f64 + x_offset, y]))
+								.collect();
+							plot_ui.points(
+								Points::new(points)
+									.shape(MarkerShape::Circle)
+									.radius(3.0)
+									.color(Color32::RED)
+									.name(&name),
+							);
+							if self.shade_violations {
+								for span in egui_telemetry_budget::spans(violations) {
+									EguiTelemetryWidget::shade_frames(
+										plot_ui,
+										span.start as f64 + x_offset..span.end as f64 + x_offset,
+										Color32::RED.linear_multiply(0.15),
+										&name,
+									);
+								}
+							}
+						}
+					}
+				}
+			});
+	}
+
+	/// Shades the full height of the plot between the frames in `frames`.
+	///
+	/// A `VLine` as wide as the frames, since it only adds to the X bounds, and auto-bounds shouldn't grow to fit the shading.
+	fn shade_frames(plot_ui: &mut PlotUi, frames: Range<f64>, color: Color32, name: &str) {
+		let x0 = plot_ui
+			.screen_from_plot(PlotPoint::new(frames.start, 0.0))
+			.x;
+		let x1 = plot_ui.screen_from_plot(PlotPoint::new(frames.end, 0.0)).x;
+		plot_ui.vline(
+			VLine::new(0.5 * (frames.start + frames.end))
+				.stroke(Stroke::new(x1 - x0, color))
+				.name(name),
+		);
+	}
+
+	/// Checks every trace with a budget against its threshold.
+	///
+	/// Returns the violating frames for each trace, as indices into its samples.
+	fn budget_violations(
+		&self,
+		traces_info: &[TraceInfo],
+		samples: &[EguiTraceSamples],
+	) -> Vec<Vec<usize>> {
+		traces_info
+			.iter()
+			.zip(samples.iter())
+			.map(|(ti, trace_samples)| {
+				let budget = self
+					.trace_configs
+					.get(ti.id())
+					.and_then(|tc| tc.budget.as_ref());
+				let limits = budget.and_then(|budget| {
+					traces_info
+						.iter()
+						.position(|ti| ti.id() == budget)
+						.map(|i| &samples[i].ys)
+				});
+				match limits {
+					Some(limits) => egui_telemetry_budget::violations(&trace_samples.ys, limits),
+					None => Vec::new(),
 				}
 			})
-			//.map(|(i, f)| [i as f64, *f as f64])
+			.collect()
+	}
+
+	/// Counts the new violations in the live telemetry, no matter what is shown.
+	fn update_budgets(&mut self, live: &EguiTelemetrySnapshot) {
+		let first_frame = live.x_offset as u64;
+		let violations = self.budget_violations(&live.traces_info, &live.samples);
+		for ((ti, samples), violations) in live
+			.traces_info
+			.iter()
+			.zip(live.samples.iter())
+			.zip(violations.iter())
+		{
+			let has_budget = self
+				.trace_configs
+				.get(ti.id())
+				.is_some_and(|tc| tc.budget.is_some());
+			if has_budget {
+				self.budget_counters
+					.entry(ti.id().to_string())
+					.or_default()
+					.update(violations, first_frame, samples.ys.len());
+			} else {
+				self.budget_counters.remove(ti.id());
+			}
+		}
+	}
+
+	fn trace_budget(ui: &mut Ui, tc: &mut TraceConfig, id: &str, thresholds: &[(String, String)]) {
+		ui.horizontal(|ui| {
+			ui.checkbox(&mut tc.threshold, "Threshold");
+			if tc.threshold {
+				return;
+			}
+			let selected = tc
+				.budget
+				.as_ref()
+				.and_then(|budget| thresholds.iter().find(|(tid, _)| tid == budget))
+				.map(|(_, name)| name.as_str())
+				.unwrap_or("No budget");
+			egui::ComboBox::from_id_source(("trace_budget", id))
+				.selected_text(selected)
+				.show_ui(ui, |ui| {
+					ui.selectable_value(&mut tc.budget, None, "No budget");
+					for (tid, name) in thresholds.iter() {
+						ui.selectable_value(&mut tc.budget, Some(tid.clone()), name);
+					}
+				});
+		});
+	}
+
+	/// Draws `id` as a threshold line.
+	pub fn set_threshold(&mut self, id: &str, threshold: bool) {
+		self.trace_config(id).threshold = threshold;
+	}
+
+	/// Checks `id` against the threshold trace `threshold_id`, marking frames over budget.
+	pub fn set_budget(&mut self, id: &str, threshold_id: Option<&str>) {
+		self.trace_config(id).budget = threshold_id.map(|t| t.to_string());
+	}
+
+	fn show_view_selection(&mut self, ui: &mut Ui, traces_info: &[TraceInfo]) {
+		ui.selectable_value(&mut self.view, EguiTelemetryView::Lines, "Lines");
+		ui.selectable_value(&mut self.view, EguiTelemetryView::Histogram, "Histogram");
+		ui.selectable_value(&mut self.view, EguiTelemetryView::BoxPlot, "Box");
+		ui.selectable_value(&mut self.view, EguiTelemetryView::Cdf, "CDF");
+		match self.view {
+			EguiTelemetryView::Histogram => {
+				ui.separator();
+				let bins = self.histogram_bins.get_or_insert(DEFAULT_HISTOGRAM_BINS);
+				ui.add(
+					egui::DragValue::new(bins)
+						.clamp_range(1..=256)
+						.prefix("Bins: "),
+				);
+			},
+			EguiTelemetryView::Cdf => {
+				ui.separator();
+				let selected = self
+					.cdf_reference
+					.as_ref()
+					.and_then(|id| traces_info.iter().find(|ti| ti.id() == id))
+					.map(|ti| ti.name().to_string())
+					.unwrap_or_else(|| "No reference".to_string());
+				egui::ComboBox::from_id_source("cdf_reference")
+					.selected_text(selected)
+					.show_ui(ui, |ui| {
+						ui.selectable_value(&mut self.cdf_reference, None, "No reference");
+						for ti in traces_info.iter() {
+							ui.selectable_value(
+								&mut self.cdf_reference,
+								Some(ti.id().to_string()),
+								ti.name(),
+							);
+						}
+					});
+			},
+			EguiTelemetryView::Lines | EguiTelemetryView::BoxPlot => {},
+		}
+	}
+
+	fn show_distribution(
+		&mut self,
+		ui: &mut Ui,
+		traces_info: &[TraceInfo],
+		samples: &[EguiTraceSamples],
+		view: EguiTelemetryView,
+	) {
+		let bins = self.histogram_bins.unwrap_or(DEFAULT_HISTOGRAM_BINS);
+		// the reference is usually a budget, e.g. the target frame time
+		let reference = self.cdf_reference.as_ref().and_then(|id| {
+			traces_info
+				.iter()
+				.zip(samples.iter())
+				.find(|(ti, _)| ti.id() == id)
+				.and_then(|(_, samples)| samples.ys.iter().rev().flatten().next().copied())
+		});
+
+		let mut fractions = Vec::new();
+		Plot::new(match view {
+			EguiTelemetryView::Histogram => "telemetry_histogram",
+			EguiTelemetryView::BoxPlot => "telemetry_box_plot",
+			_ => "telemetry_cdf",
+		})
+		.legend(Legend::default())
+		.show(ui, |plot_ui| {
+			for (i, ti) in traces_info.iter().enumerate() {
+				let tc = match self.trace_configs.get(ti.id()) {
+					Some(tc) if tc.enabled => tc,
+					_ => continue,
+				};
+				let stats = match self.stats.get(ti.id()) {
+					Some(stats) if stats.count() > 0 => stats,
+					_ => continue,
+				};
+				match view {
+					EguiTelemetryView::Histogram => {
+						let bars = egui_telemetry_distribution::histogram(stats.sorted(), bins)
+							.into_iter()
+							.map(|bin| {
+								Bar::new(bin.start + 0.5 * bin.width, bin.count as f64)
+									.width(bin.width)
+							})
+							.collect();
+						plot_ui.bar_chart(BarChart::new(bars).color(tc.color).name(ti.name()));
+					},
+					EguiTelemetryView::BoxPlot => {
+						let spread = BoxSpread::new(
+							stats.min().unwrap_or_default(),
+							stats.percentile(0.25).unwrap_or_default(),
+							stats.percentile(0.5).unwrap_or_default(),
+							stats.percentile(0.75).unwrap_or_default(),
+							stats.max().unwrap_or_default(),
+						);
+						let elem = BoxElem::new(i as f64, spread).name(ti.name());
+						plot_ui.box_plot(BoxPlot::new(vec![elem]).color(tc.color).name(ti.name()));
+					},
+					_ => {
+						let points: PlotPoints =
+							egui_telemetry_distribution::cdf_points(stats.sorted()).into();
+						plot_ui.line(Line::new(points).color(tc.color).name(ti.name()));
+						if let Some(reference) = reference {
+							fractions.push((
+								ti.name().to_string(),
+								tc.color,
+								egui_telemetry_distribution::fraction_at_or_below(
+									stats.sorted(),
+									reference,
+								),
+							));
+						}
+					},
+				}
+			}
+			if view == EguiTelemetryView::Cdf {
+				if let Some(reference) = reference {
+					plot_ui.vline(VLine::new(reference).color(Color32::WHITE));
+				}
+			}
+		});
+
+		for (name, color, fraction) in fractions {
+			ui.colored_label(
+				color,
+				format!(
+					"{}: {:.1}% at or under the reference",
+					name,
+					100.0 * fraction
+				),
+			);
+		}
+	}
+	/// Saves the trace settings and presets to a writable layer of the `system` filesystem.
+	pub fn save_config(&self, system: &mut System, path: &str) -> anyhow::Result<()> {
+		let config = EguiTelemetryConfig {
+			traces:  self
+				.trace_configs
+				.iter()
+				.map(|(id, tc)| (id.clone(), tc.clone()))
+				.collect(),
+			presets: self.presets.clone(),
+		};
+		config.save(system, path)
+	}
+
+	/// Restores what `save_config` wrote, traces that aren't in the file keep their settings.
+	pub fn load_config(&mut self, system: &mut System, path: &str) -> anyhow::Result<()> {
+		let config = EguiTelemetryConfig::load(system, path)?;
+		self.trace_configs.extend(config.traces);
+		self.presets = config.presets;
+		self.active_preset = None;
+		Ok(())
+	}
+
+	/// Remembers the current trace settings as `name`, replacing an existing preset.
+	pub fn store_preset(&mut self, name: &str) {
+		let preset = self
+			.trace_configs
+			.iter()
+			.map(|(id, tc)| (id.clone(), tc.clone()))
 			.collect();
-		Line::new(points)
+		self.presets.insert(name.to_string(), preset);
+		self.active_preset = Some(name.to_string());
 	}
-	// borrowed directly from egui
-	fn next_auto_color(&mut self) -> Color32 {
-		let i = self.next_auto_color_idx;
-		self.next_auto_color_idx += 1;
+
+	/// Switches to the preset `name`, traces it doesn't know about are disabled.
+	///
+	/// Returns false if there is no such preset.
+	pub fn apply_preset(&mut self, name: &str) -> bool {
+		let preset = match self.presets.get(name) {
+			Some(preset) => preset,
+			None => return false,
+		};
+		for tc in self.trace_configs.values_mut() {
+			tc.enabled = false;
+		}
+		for (id, tc) in preset.iter() {
+			self.trace_configs.insert(id.clone(), tc.clone());
+		}
+		self.active_preset = Some(name.to_string());
+		true
+	}
+
+	pub fn remove_preset(&mut self, name: &str) -> bool {
+		if self.active_preset.as_deref() == Some(name) {
+			self.active_preset = None;
+		}
+		self.presets.remove(name).is_some()
+	}
+
+	pub fn preset_names(&self) -> impl Iterator<Item = &str> {
+		self.presets.keys().map(|n| n.as_str())
+	}
+
+	fn show_presets(&mut self, ui: &mut Ui) {
+		ui.horizontal(|ui| {
+			let mut selected = None;
+			egui::ComboBox::from_id_source("telemetry_presets")
+				.selected_text(self.active_preset.as_deref().unwrap_or("Presets"))
+				.show_ui(ui, |ui| {
+					for name in self.presets.keys() {
+						let active = self.active_preset.as_deref() == Some(name.as_str());
+						if ui.selectable_label(active, name).clicked() {
+							selected = Some(name.clone());
+						}
+					}
+				});
+			if let Some(name) = selected {
+				self.apply_preset(&name);
+			}
+
+			if let Some(name) = self.active_preset.clone() {
+				if ui.button("Update").clicked() {
+					self.store_preset(&name);
+				}
+				if ui.button("Remove").clicked() {
+					self.remove_preset(&name);
+				}
+			}
+
+			ui.separator();
+			ui.add(egui::TextEdit::singleline(&mut self.new_preset_name).desired_width(100.0));
+			let name = self.new_preset_name.trim().to_string();
+			if ui
+				.add_enabled(!name.is_empty(), egui::Button::new("Save as"))
+				.clicked()
+			{
+				self.store_preset(&name);
+				self.new_preset_name.clear();
+			}
+		});
+	}
+
+	fn show_stats_table(
+		&self,
+		ui: &mut Ui,
+		traces_info: &[TraceInfo],
+		samples: &[EguiTraceSamples],
+	) {
+		let format = |v: Option<f64>| match v {
+			Some(v) => format!("{:.4}", v),
+			None => "-".to_string(),
+		};
+
+		egui::ScrollArea::vertical().show(ui, |ui| {
+			egui::Grid::new("stats_grid")
+				.striped(true)
+				.num_columns(9)
+				.show(ui, |ui| {
+					for h in [
+						"Trace", "Current", "Min", "Max", "Mean", "Std Dev", "p50", "p95", "p99",
+					] {
+						ui.strong(h);
+					}
+					ui.end_row();
+
+					for (ti, samples) in traces_info.iter().zip(samples.iter()) {
+						let (tc, stats) =
+							match (self.trace_configs.get(ti.id()), self.stats.get(ti.id())) {
+								(Some(tc), Some(stats)) => (tc, stats),
+								_ => continue,
+							};
+						if samples.kind == EguiTraceKind::Unsupported {
+							continue;
+						}
+
+						ui.colored_label(tc.color, ti.name());
+						ui.label(format(stats.current()));
+						ui.label(format(stats.min()));
+						ui.label(format(stats.max()));
+						ui.label(format(stats.mean()));
+						ui.label(format(stats.std_dev()));
+						ui.label(format(stats.percentile(0.5)));
+						ui.label(format(stats.percentile(0.95)));
+						ui.label(format(stats.percentile(0.99)));
+						ui.end_row();
+					}
+				});
+		});
+	}
+
+	/// Moves the statistics window of every enabled trace along with the scope.
+	fn update_stats(&mut self, traces_info: &[TraceInfo], samples: &[EguiTraceSamples]) {
+		let first_frame = self.x_offset as u64;
+		let frames = match (self.stats_scope(), &self.visible_frames) {
+			(EguiStatsScope::Visible, Some(visible)) => {
+				visible.start.max(0.0).floor() as u64..visible.end.max(0.0).ceil() as u64 + 1
+			},
+			_ => 0..u64::MAX,
+		};
+
+		for (ti, samples) in traces_info.iter().zip(samples.iter()) {
+			let tc = self.trace_config(ti.id());
+			if !tc.enabled || samples.kind == EguiTraceKind::Unsupported {
+				self.stats.remove(ti.id());
+				continue;
+			}
+			let stats = self.stats.entry(ti.id().to_string()).or_default();
+			stats.update(&samples.ys, first_frame, frames.clone());
+		}
+	}
+
+	/// The other views plot values instead of frames, so they always cover the buffer.
+	fn stats_scope(&self) -> EguiStatsScope {
+		match self.view {
+			EguiTelemetryView::Lines => self.stats_scope,
+			_ => EguiStatsScope::Buffer,
+		}
+	}
+
+	fn trace_config(&mut self, id: &str) -> &mut TraceConfig {
+		self.trace_configs
+			.entry(id.to_string())
+			.or_insert_with(|| TraceConfig {
+				color: EguiTelemetryWidget::auto_color(id),
+				..Default::default()
+			})
+	}
+
+	fn trace_checkbox(ui: &mut Ui, tc: &mut TraceConfig, name: &str) {
+		ui.horizontal(|ui| {
+			egui::color_picker::color_edit_button_srgba(
+				ui,
+				&mut tc.color,
+				egui::color_picker::Alpha::Opaque,
+			);
+			ui.checkbox(&mut tc.enabled, name);
+		});
+		ui.add(
+			egui::DragValue::new(&mut tc.subplot)
+				.clamp_range(0..=MAX_SUBPLOTS - 1)
+				.prefix("Plot "),
+		);
+		ui.checkbox(&mut tc.shade_gaps, "Shade gaps");
+	}
+
+	/// The enabled trace closest to `pointer` vertically, at the frame under the pointer.
+	fn hovered_trace(
+		&mut self,
+		traces_info: &[TraceInfo],
+		samples: &[EguiTraceSamples],
+		subplot: usize,
+		x_offset: f64,
+		pointer: egui::plot::PlotPoint,
+		max_distance: f64,
+	) -> Option<usize> {
+		let frame = (pointer.x - x_offset).floor();
+		if frame < 0.0 {
+			return None;
+		}
+		let frame = frame as usize;
+		let mut hovered = None;
+		let mut best_distance = max_distance;
+		for (i, (ti, samples)) in traces_info.iter().zip(samples.iter()).enumerate() {
+			let tc = self.trace_config(ti.id());
+			if !tc.enabled || tc.subplot != subplot {
+				continue;
+			}
+			if let Some(Some(y)) = samples.ys.get(frame) {
+				let distance = (y - pointer.y).abs();
+				if distance <= best_distance {
+					best_distance = distance;
+					hovered = Some(i);
+				}
+			}
+		}
+		hovered
+	}
+
+	/// Derived from the trace id, so a trace keeps its color across frames and runs.
+	fn auto_color(id: &str) -> Color32 {
+		// FNV-1a, the std hashers don't promise to be stable
+		let mut hash: u32 = 0x811c9dc5;
+		for b in id.bytes() {
+			hash ^= b as u32;
+			hash = hash.wrapping_mul(0x01000193);
+		}
 		let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0; // 0.61803398875
-		let h = i as f32 * golden_ratio;
+		let h = ((hash % 1024) as f32 * golden_ratio).fract();
 		egui::epaint::Hsva::new(h, 0.85, 0.5, 1.0).into()
 	}
 }
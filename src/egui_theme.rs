@@ -0,0 +1,165 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use oml_game::system::System;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::filesystem;
+
+/// Visuals, spacing and text styles for egui, usually loaded from a RON file.
+///
+/// Everything is optional, and only the parts that are given replace the base style.
+///
+/// ```ron
+/// (
+///     visuals: Some((dark_mode: true)),
+///     text_styles: Some({
+///         Heading: (size: 30.0, family: Proportional),
+///         Name("Context"): (size: 23.0, family: Proportional),
+///     }),
+/// )
+/// ```
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EguiTheme {
+	pub visuals:             Option<egui::Visuals>,
+	pub spacing:             Option<egui::style::Spacing>,
+	pub text_styles:         Option<BTreeMap<egui::TextStyle, egui::FontId>>,
+	pub override_text_style: Option<egui::TextStyle>,
+}
+
+impl EguiTheme {
+	pub fn from_ron(source: &str) -> anyhow::Result<Self> {
+		Ok(ron::from_str(source)?)
+	}
+
+	pub fn to_ron(&self) -> anyhow::Result<String> {
+		Ok(ron::ser::to_string_pretty(
+			self,
+			ron::ser::PrettyConfig::default(),
+		)?)
+	}
+
+	pub fn load(system: &mut System, path: &str) -> anyhow::Result<Self> {
+		let source = filesystem::read_string(system, path)?;
+		EguiTheme::from_ron(&source)
+	}
+
+	/// Applies the theme on top of `base`, so parts removed from the theme go back to the base style.
+	pub fn apply(&self, ctx: &egui::Context, base: &egui::Style) {
+		ctx.set_style(self.style(base));
+	}
+
+	pub fn style(&self, base: &egui::Style) -> egui::Style {
+		let mut style = base.clone();
+		if let Some(visuals) = &self.visuals {
+			style.visuals = visuals.clone();
+		}
+		if let Some(spacing) = &self.spacing {
+			style.spacing = spacing.clone();
+		}
+		if let Some(text_styles) = &self.text_styles {
+			style.text_styles = text_styles.clone();
+		}
+		if self.override_text_style.is_some() {
+			style.override_text_style = self.override_text_style.clone();
+		}
+		style
+	}
+}
+
+/// Remembers where the theme came from, so development builds can pick up changes to the file.
+#[derive(Debug)]
+pub(crate) struct EguiThemeWatcher {
+	path:            String,
+	source:          String,
+	reload_interval: Duration,
+	last_check:      Instant,
+	last_reread:     Instant,
+}
+
+impl EguiThemeWatcher {
+	const REREAD_FACTOR: u32 = 5;
+
+	pub fn new(path: &str, source: String) -> Self {
+		Self {
+			path: path.to_string(),
+			source,
+			reload_interval: Duration::from_secs(1),
+			last_check: Instant::now(),
+			last_reread: Instant::now(),
+		}
+	}
+
+	pub fn set_reload_interval(&mut self, reload_interval: Duration) {
+		self.reload_interval = reload_interval;
+	}
+
+	/// Returns the new theme if the file changed since the last check.
+	///
+	/// Broken files are reported, and the current theme is kept.
+	pub fn poll(&mut self, system: &mut System) -> Option<EguiTheme> {
+		if self.last_check.elapsed() < self.reload_interval {
+			return None;
+		}
+		self.last_check = Instant::now();
+
+		// the filesystem has no modification times, and edits of the same size only show in the content,
+		// so that is reread less often
+		let size = filesystem::size(system, &self.path);
+		if size == Some(self.source.len())
+			&& self.last_reread.elapsed() < EguiThemeWatcher::REREAD_FACTOR * self.reload_interval
+		{
+			return None;
+		}
+		self.last_reread = Instant::now();
+
+		let source = match filesystem::read_string(system, &self.path) {
+			Ok(source) => source,
+			Err(e) => {
+				tracing::warn!("Couldn't reread theme {}: {}", &self.path, &e);
+				return None;
+			},
+		};
+		if source == self.source {
+			return None;
+		}
+		self.source = source;
+
+		match EguiTheme::from_ron(&self.source) {
+			Ok(theme) => {
+				tracing::info!("Reloaded theme {}", &self.path);
+				Some(theme)
+			},
+			Err(e) => {
+				tracing::warn!("Couldn't parse theme {}: {}", &self.path, &e);
+				None
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn themes_start_from_the_base_style() {
+		let base = egui::Style::default();
+		let theme = EguiTheme::from_ron(
+			"(visuals: Some((dark_mode: false)), override_text_style: Some(Monospace))",
+		)
+		.unwrap();
+		let style = theme.style(&base);
+		assert!(!style.visuals.dark_mode);
+		assert_eq!(style.override_text_style, Some(egui::TextStyle::Monospace));
+		assert_eq!(style.spacing.item_spacing, base.spacing.item_spacing);
+
+		// removing parts from the theme brings back the base style
+		let style = EguiTheme::from_ron("()").unwrap().style(&base);
+		assert_eq!(style.visuals.dark_mode, base.visuals.dark_mode);
+		assert_eq!(style.override_text_style, None);
+	}
+}
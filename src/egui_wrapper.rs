@@ -1,18 +1,51 @@
-use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::sync::RwLock;
+use std::time::Duration;
 
 use egui::RawInput;
-use oml_game::math::Matrix32;
+use oml_game::math::Matrix44;
 use oml_game::math::Vector2;
 use oml_game::renderer::Color;
 use oml_game::renderer::Renderer;
-use oml_game::renderer::Texture;
 use oml_game::system::System;
 use oml_game::window::WindowUpdateContext;
 
+use crate::egui_fonts::EguiFonts;
+use crate::egui_frame::EguiFrame;
+use crate::egui_frame::EguiFrameExchange;
+use crate::egui_frame::EguiFrameLayer;
+use crate::egui_memory;
+use crate::egui_painter::EguiPainter;
+use crate::egui_theme::EguiThemeWatcher;
+use crate::filesystem;
+use crate::EguiColorSpace;
+use crate::EguiFont;
+use crate::EguiLayerMap;
+use crate::EguiModal;
+use crate::EguiOverlay;
+use crate::EguiOverlayState;
+use crate::EguiRenderTarget;
+use crate::EguiScreen;
+use crate::EguiScreenLayout;
+use crate::EguiTheme;
+use crate::EguiWrapperConfig;
+
+/// How much of the pointer a context gets when several of them share the screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum EguiPointerRouting {
+	Full,
+	/// Hover only, clicks are left for someone else.
+	MoveOnly,
+	/// Covered by another context.
+	Gone,
+}
+
 #[derive(Debug, Default)]
 pub struct EguiWrapper {
-	inner: RwLock<EguiWrapperInner>,
+	inner:   RwLock<EguiWrapperInner>,
+	painter: Mutex<EguiPainter>,
+	frames:  EguiFrameExchange,
 }
 
 impl EguiWrapper {
@@ -21,11 +54,79 @@ impl EguiWrapper {
 		inner.setup(pixels_per_point)
 	}
 
+	/// Validates `config` against the `renderer`, and applies it.
+	///
+	/// Replaces calling `setup`, `set_effect_id`, `set_layer_id`, `set_color`, and `set_input_disabled` one by one.
+	pub fn configure(
+		&mut self,
+		config: EguiWrapperConfig,
+		renderer: &mut Renderer,
+	) -> anyhow::Result<()> {
+		config.validate(renderer)?;
+		let mut inner = self.inner.write().unwrap();
+		inner.configure(config)
+	}
+
+	/// Tints everything egui draws, `color` has straight alpha.
 	pub fn set_color(&mut self, color: &Color) {
 		let mut inner = self.inner.write().unwrap();
 		inner.set_color(color);
 	}
 
+	pub fn set_color_space(&mut self, color_space: EguiColorSpace) {
+		let mut inner = self.inner.write().unwrap();
+		inner.set_color_space(color_space);
+	}
+
+	/// Loads a font file from the `system` filesystem, and adds it to the families given in `font`.
+	pub fn load_font(&mut self, system: &mut System, font: EguiFont) -> anyhow::Result<()> {
+		let mut inner = self.inner.write().unwrap();
+		inner.load_font(system, font)
+	}
+
+	/// Rereads all fonts loaded via `load_font`.
+	pub fn reload_fonts(&mut self, system: &mut System) -> anyhow::Result<()> {
+		let mut inner = self.inner.write().unwrap();
+		inner.reload_fonts(system)
+	}
+
+	pub fn remove_font(&mut self, name: &str) -> bool {
+		let mut inner = self.inner.write().unwrap();
+		inner.remove_font(name)
+	}
+
+	pub fn set_theme(&mut self, theme: &EguiTheme) {
+		let mut inner = self.inner.write().unwrap();
+		inner.set_theme(theme);
+	}
+
+	/// Loads and applies a RON theme from the `system` filesystem.
+	///
+	/// In development builds the file is watched, and changes are applied in `run`.
+	pub fn load_theme(&mut self, system: &mut System, path: &str) -> anyhow::Result<()> {
+		let mut inner = self.inner.write().unwrap();
+		inner.load_theme(system, path)
+	}
+
+	pub fn set_theme_reload_interval(&mut self, reload_interval: Duration) {
+		let mut inner = self.inner.write().unwrap();
+		inner.set_theme_reload_interval(reload_interval);
+	}
+
+	/// Saves egui's memory, i.e. window positions and open states, to a writable layer of the `system` filesystem.
+	pub fn save_memory(&self, system: &mut System, path: &str) -> anyhow::Result<()> {
+		let inner = self.inner.read().unwrap();
+		egui_memory::save(&inner.egui_ctx, system, path)
+	}
+
+	/// Restores egui's memory saved via `save_memory`.
+	///
+	/// Returns false if there was nothing compatible to restore.
+	pub fn load_memory(&mut self, system: &mut System, path: &str) -> bool {
+		let inner = self.inner.read().unwrap();
+		egui_memory::load(&inner.egui_ctx, system, path)
+	}
+
 	pub fn toggle_input(&mut self) -> bool {
 		let mut inner = self.inner.write().unwrap();
 		inner.toggle_input()
@@ -47,65 +148,203 @@ impl EguiWrapper {
 	}
 
 	pub fn input_disabled(&self) -> bool {
-		let mut inner = self.inner.read().unwrap();
+		let inner = self.inner.read().unwrap();
 		inner.input_disabled()
 	}
 
+	/// Makes the wrapper a fading overlay, see `EguiOverlay`.
+	pub fn set_overlay(&mut self, overlay: Option<EguiOverlay>) {
+		let mut inner = self.inner.write().unwrap();
+		inner.set_overlay(overlay);
+	}
+
+	pub fn show_overlay(&mut self) {
+		let mut inner = self.inner.write().unwrap();
+		inner.show_overlay();
+	}
+
+	pub fn hide_overlay(&mut self) {
+		let mut inner = self.inner.write().unwrap();
+		inner.hide_overlay();
+	}
+
+	/// Returns true if the overlay is on its way in.
+	pub fn toggle_overlay(&mut self) -> bool {
+		let mut inner = self.inner.write().unwrap();
+		inner.toggle_overlay()
+	}
+
+	/// True if an `EguiModal` was shown in the last `run`, gameplay should pause then.
+	///
+	/// While a modal is active `update` takes all mouse buttons and keys.
+	pub fn is_modal_active(&self) -> bool {
+		let inner = self.inner.read().unwrap();
+		inner.is_modal_active()
+	}
+
+	/// `None` if the wrapper isn't an overlay.
+	pub fn overlay_state(&self) -> Option<EguiOverlayState> {
+		let inner = self.inner.read().unwrap();
+		inner.overlay_state()
+	}
+
 	pub fn set_effect_id(&mut self, effect_id: u16) {
 		let mut inner = self.inner.write().unwrap();
 		inner.set_effect_id(effect_id);
 	}
+	/// Draws all egui output into `layer_id`.
 	pub fn set_layer_id(&mut self, layer_id: u8) {
 		let mut inner = self.inner.write().unwrap();
 		inner.set_layer_id(layer_id);
 	}
+
+	/// Draws each egui `Order` into its own layer.
+	pub fn set_layer_map(&mut self, layer_map: EguiLayerMap) {
+		let mut inner = self.inner.write().unwrap();
+		inner.set_layer_map(layer_map);
+	}
+	/// Paint into a canvas texture instead of the screen, or back.
+	///
+	/// Canvases only get the egui textures sent while they are the target, so pick the canvas before the first `run`.
+	pub fn set_render_target(&mut self, render_target: EguiRenderTarget) {
+		let mut inner = self.inner.write().unwrap();
+		inner.set_render_target(render_target);
+	}
+
+	/// Where the pointer hits the canvas, in texture coordinates, or `None` if it's not on it.
+	///
+	/// Only used with `EguiRenderTarget::Canvas`, the game does the hit test against its geometry.
+	pub fn set_pointer_uv(&mut self, pointer_uv: Option<Vector2>) {
+		let mut inner = self.inner.write().unwrap();
+		inner.set_pointer_uv(pointer_uv);
+	}
+
 	pub fn update(&mut self, wuc: &mut WindowUpdateContext) -> anyhow::Result<()> {
 		let mut inner = self.inner.write().unwrap();
-		inner.update(wuc)
+		inner.update(wuc, EguiPointerRouting::Full)
 	}
 
-	pub fn run<F>(&self, system: &mut System, mut f: F) -> anyhow::Result<()>
-	where
-		F: FnMut(&egui::Context) -> anyhow::Result<()>,
-	{
+	pub(crate) fn update_routed(
+		&mut self,
+		wuc: &mut WindowUpdateContext,
+		routing: EguiPointerRouting,
+	) -> anyhow::Result<()> {
 		let mut inner = self.inner.write().unwrap();
-		inner.run(system, f)
+		inner.update(wuc, routing)
 	}
 
-	pub fn render(&mut self, system: &mut System, renderer: &mut Renderer) -> anyhow::Result<()> {
+	/// True if egui wanted the pointer in the last frame, e.g. because it's over a window or dragging something.
+	pub fn wants_pointer_input(&self) -> bool {
+		let inner = self.inner.read().unwrap();
+		inner.wants_pointer_input()
+	}
+
+	/// Origin and transform of the egui layer in render coordinates.
+	pub fn set_screen(&mut self, screen: EguiScreen) {
 		let mut inner = self.inner.write().unwrap();
-		inner.render(system, renderer)
+		inner.set_screen(screen);
 	}
-	fn gather_input(&mut self) -> RawInput {
+
+	/// The mvp egui needs, based on the `EguiScreen` and the viewport of `renderer`.
+	pub fn mvp_matrix(&self, renderer: &Renderer) -> Matrix44 {
+		let inner = self.inner.read().unwrap();
+		inner.mvp_matrix(renderer.viewport_size())
+	}
+
+	/// Shades the unsafe areas, see `EguiScreen::with_safe_area`.
+	pub fn set_show_safe_area(&mut self, show_safe_area: bool) {
 		let mut inner = self.inner.write().unwrap();
-		inner.gather_input()
+		inner.set_show_safe_area(show_safe_area);
+	}
+
+	/// Where the egui screen ends up in a viewport of `viewport_size` pixels.
+	pub fn screen_layout(&self, viewport_size: &Vector2) -> EguiScreenLayout {
+		let inner = self.inner.read().unwrap();
+		inner.screen_layout(viewport_size)
+	}
+
+	/// Sets the mvp egui needs, call before `render`.
+	pub fn set_mvp_matrix(&self, renderer: &mut Renderer) {
+		let mvp = self.mvp_matrix(renderer);
+		renderer.set_mvp_matrix(&mvp);
 	}
 
-	fn paint(&mut self, system: &mut System, renderer: &mut Renderer) -> anyhow::Result<()> {
+	/// Prefixes the renderer texture names, to tell the textures of several `EguiWrapper`s apart.
+	///
+	/// Fails once `run` created textures, since they are already registered under the old names.
+	pub fn set_texture_namespace(&mut self, texture_namespace: &str) -> anyhow::Result<()> {
 		let mut inner = self.inner.write().unwrap();
-		inner.paint(system, renderer)
+		inner.set_texture_namespace(texture_namespace)
+	}
+
+	/// Lets egui draw the renderer texture `name`, e.g. via `ui.image(texture_id, size)`.
+	///
+	/// Canvas render targets don't show user textures.
+	pub fn register_user_texture(&mut self, name: &str) -> egui::TextureId {
+		let mut inner = self.inner.write().unwrap();
+		inner.register_user_texture(name)
+	}
+
+	/// Runs one egui frame, and hands the result over to `render`.
+	///
+	/// Can be called from a different thread than `render`.
+	/// If `f` fails, its error is returned and `render` keeps showing the previous frame.
+	pub fn run<F>(&self, system: &mut System, f: F) -> anyhow::Result<()>
+	where
+		F: FnMut(&egui::Context) -> anyhow::Result<()>,
+	{
+		let frame = {
+			let mut inner = self.inner.write().unwrap();
+			inner.run(system, &self.frames.viewport_size(), f)?
+		};
+		self.frames.publish(frame);
+		Ok(())
+	}
+
+	/// Paints the latest frame produced by `run`, or repaints the previous one if there is no new frame yet.
+	pub fn render(&self, system: &mut System, renderer: &mut Renderer) -> anyhow::Result<()> {
+		let mut painter = self.painter.lock().unwrap();
+		self.frames.set_viewport_size(renderer.viewport_size());
+		//tracing::debug!("Size {:?}", renderer.viewport_size());
+		if let Some(frame) = self.frames.take() {
+			painter.set_frame(frame);
+		}
+		painter.paint(system, renderer)
 	}
 
-	fn paint_mesh(&self, renderer: &mut Renderer, mesh: egui::epaint::Mesh) -> anyhow::Result<()> {
-		let mut inner = self.inner.read().unwrap();
-		inner.paint_mesh(renderer, mesh)
+	/// True if `run` produced a frame that hasn't been picked up by `render` yet.
+	pub fn has_pending_frame(&self) -> bool {
+		self.frames.has_pending()
 	}
 }
 
 #[derive(Debug, Default)]
 pub struct EguiWrapperInner {
 	egui_ctx: egui::Context,
-	shapes: Vec<egui::epaint::ClippedShape>,
-	textures_delta: egui::TexturesDelta,
 	effect_id: u16,
-	layer_id: u8,
-	texture_ids: HashMap<egui::epaint::TextureId, u16>,
+	layer_map: EguiLayerMap,
 	size: Vector2,
 	pixels_per_point: f32,
 	events: Vec<egui::Event>,
 	primary_mouse_button_was_pressed: bool,
 	input_disabled: bool,
 	color: Color,
+	color_space: EguiColorSpace,
+	fonts: EguiFonts,
+	theme_watcher: Option<EguiThemeWatcher>,
+	theme_reload_interval: Option<Duration>,
+	base_style: Option<egui::Style>,
+	render_target: EguiRenderTarget,
+	pointer_uv: Option<Vector2>,
+	texture_namespace: String,
+	textures_created: bool,
+	/// Textures of frames that failed, egui only sends them once.
+	textures_delta: egui::TexturesDelta,
+	user_textures: Arc<Vec<String>>,
+	screen: EguiScreen,
+	show_safe_area: bool,
+	overlay: Option<EguiOverlay>,
+	modal_active: bool,
 }
 
 impl EguiWrapperInner {
@@ -114,10 +353,105 @@ impl EguiWrapperInner {
 		Ok(())
 	}
 
+	pub fn configure(&mut self, config: EguiWrapperConfig) -> anyhow::Result<()> {
+		// an empty namespace keeps the one set before, e.g. by the `EguiWrapperManager`
+		if !config.texture_namespace().is_empty() {
+			self.set_texture_namespace(config.texture_namespace())?;
+		}
+		self.setup(config.pixels_per_point())?;
+		self.layer_map = *config.layer_map();
+		if let Some(effect_id) = config.effect_id() {
+			self.effect_id = effect_id;
+		}
+		self.color = *config.color();
+		self.color_space = config.color_space();
+		self.input_disabled = config.input_disabled();
+		self.screen = config.screen().clone();
+		self.overlay = config.overlay().cloned();
+		if let Some(visuals) = config.visuals() {
+			if let Some(base) = &mut self.base_style {
+				base.visuals = visuals.clone();
+			}
+			self.egui_ctx.set_visuals(visuals.clone());
+		}
+		if let Some(fonts) = config.fonts() {
+			self.fonts.set_base(fonts.clone());
+			self.egui_ctx.set_fonts(self.fonts.definitions());
+		}
+		Ok(())
+	}
+
+	pub fn load_font(&mut self, system: &mut System, font: EguiFont) -> anyhow::Result<()> {
+		self.fonts.load(system, font)?;
+		self.egui_ctx.set_fonts(self.fonts.definitions());
+		Ok(())
+	}
+
+	pub fn reload_fonts(&mut self, system: &mut System) -> anyhow::Result<()> {
+		let r = self.fonts.reload(system);
+		// fonts that did reload should still be picked up
+		self.egui_ctx.set_fonts(self.fonts.definitions());
+		r
+	}
+
+	pub fn remove_font(&mut self, name: &str) -> bool {
+		let removed = self.fonts.remove(name);
+		if removed {
+			self.egui_ctx.set_fonts(self.fonts.definitions());
+		}
+		removed
+	}
+
 	pub fn set_color(&mut self, color: &Color) {
 		self.color = *color;
 	}
 
+	pub fn set_color_space(&mut self, color_space: EguiColorSpace) {
+		self.color_space = color_space;
+	}
+
+	pub fn set_theme(&mut self, theme: &EguiTheme) {
+		self.apply_theme(theme);
+		self.theme_watcher = None;
+	}
+
+	/// The style from before the first theme is the base for all themes.
+	fn apply_theme(&mut self, theme: &EguiTheme) {
+		let base = self
+			.base_style
+			.get_or_insert_with(|| (*self.egui_ctx.style()).clone());
+		theme.apply(&self.egui_ctx, base);
+	}
+
+	pub fn load_theme(&mut self, system: &mut System, path: &str) -> anyhow::Result<()> {
+		let source = filesystem::read_string(system, path)?;
+		let theme = EguiTheme::from_ron(&source)?;
+		self.apply_theme(&theme);
+		let mut theme_watcher = EguiThemeWatcher::new(path, source);
+		if let Some(reload_interval) = self.theme_reload_interval {
+			theme_watcher.set_reload_interval(reload_interval);
+		}
+		self.theme_watcher = Some(theme_watcher);
+		Ok(())
+	}
+
+	pub fn set_theme_reload_interval(&mut self, reload_interval: Duration) {
+		self.theme_reload_interval = Some(reload_interval);
+		if let Some(theme_watcher) = &mut self.theme_watcher {
+			theme_watcher.set_reload_interval(reload_interval);
+		}
+	}
+
+	fn poll_theme(&mut self, system: &mut System) {
+		let theme = match &mut self.theme_watcher {
+			Some(theme_watcher) => theme_watcher.poll(system),
+			None => None,
+		};
+		if let Some(theme) = theme {
+			self.apply_theme(&theme);
+		}
+	}
+
 	pub fn toggle_input(&mut self) -> bool {
 		self.input_disabled = !self.input_disabled;
 
@@ -140,24 +474,163 @@ impl EguiWrapperInner {
 		self.input_disabled
 	}
 
+	/// Input is also off while the overlay is hidden or on its way out.
+	fn accepts_input(&self) -> bool {
+		!self.input_disabled
+			&& self
+				.overlay
+				.as_ref()
+				.is_none_or(|overlay| overlay.is_showing())
+	}
+
+	pub fn set_overlay(&mut self, overlay: Option<EguiOverlay>) {
+		self.overlay = overlay;
+	}
+
+	pub fn show_overlay(&mut self) {
+		match &mut self.overlay {
+			Some(overlay) => overlay.show(),
+			None => tracing::warn!("show_overlay without overlay"),
+		}
+	}
+
+	pub fn hide_overlay(&mut self) {
+		match &mut self.overlay {
+			Some(overlay) => overlay.hide(),
+			None => tracing::warn!("hide_overlay without overlay"),
+		}
+	}
+
+	pub fn toggle_overlay(&mut self) -> bool {
+		match &mut self.overlay {
+			Some(overlay) => overlay.toggle(),
+			None => {
+				tracing::warn!("toggle_overlay without overlay");
+				false
+			},
+		}
+	}
+
+	pub fn overlay_state(&self) -> Option<EguiOverlayState> {
+		self.overlay.as_ref().map(|overlay| overlay.state())
+	}
+
+	pub fn is_modal_active(&self) -> bool {
+		self.modal_active && self.accepts_input()
+	}
+
 	pub fn set_effect_id(&mut self, effect_id: u16) {
 		self.effect_id = effect_id;
 	}
 	pub fn set_layer_id(&mut self, layer_id: u8) {
-		self.layer_id = layer_id;
+		self.layer_map = EguiLayerMap::new(layer_id);
 	}
-	pub fn update(&mut self, wuc: &mut WindowUpdateContext) -> anyhow::Result<()> {
-		if !self.input_disabled {
-			let mut cursor_pos = Vector2::zero();
-
-			cursor_pos.x = 1.0 * (wuc.mouse_pos.x * wuc.window_size.x - 0.5 * wuc.window_size.x);
-			cursor_pos.y = -1.0 * (wuc.mouse_pos.y * wuc.window_size.y - 0.5 * wuc.window_size.y);
+	pub fn set_layer_map(&mut self, layer_map: EguiLayerMap) {
+		self.layer_map = layer_map;
+	}
+	pub fn set_render_target(&mut self, render_target: EguiRenderTarget) {
+		self.render_target = render_target;
+	}
+	pub fn set_pointer_uv(&mut self, pointer_uv: Option<Vector2>) {
+		self.pointer_uv = pointer_uv;
+	}
+	pub fn wants_pointer_input(&self) -> bool {
+		if self.is_modal_active() {
+			return true;
+		}
+		self.accepts_input()
+			&& (self.egui_ctx.wants_pointer_input() || self.egui_ctx.is_pointer_over_area())
+	}
+	pub fn set_texture_namespace(&mut self, texture_namespace: &str) -> anyhow::Result<()> {
+		if self.texture_namespace == texture_namespace {
+			return Ok(());
+		}
+		if self.textures_created {
+			anyhow::bail!(
+				"Can't change the texture namespace from {:?} to {:?}, textures were already created",
+				self.texture_namespace,
+				texture_namespace
+			);
+		}
+		self.texture_namespace = texture_namespace.to_string();
+		Ok(())
+	}
+	pub fn register_user_texture(&mut self, name: &str) -> egui::TextureId {
+		let index = match self.user_textures.iter().position(|n| n == name) {
+			Some(index) => index,
+			None => {
+				Arc::make_mut(&mut self.user_textures).push(name.to_string());
+				self.user_textures.len() - 1
+			},
+		};
+		egui::TextureId::User(index as u64)
+	}
+	pub fn set_screen(&mut self, screen: EguiScreen) {
+		self.screen = screen;
+	}
+	pub fn set_show_safe_area(&mut self, show_safe_area: bool) {
+		self.show_safe_area = show_safe_area;
+	}
+	pub fn screen_layout(&self, viewport_size: &Vector2) -> EguiScreenLayout {
+		self.screen.layout(viewport_size, self.pixels_per_point)
+	}
+	pub fn mvp_matrix(&self, viewport_size: &Vector2) -> Matrix44 {
+		self.screen.mvp_matrix(&self.screen_layout(viewport_size))
+	}
+	pub fn update(
+		&mut self,
+		wuc: &mut WindowUpdateContext,
+		routing: EguiPointerRouting,
+	) -> anyhow::Result<()> {
+		if let Some(overlay) = &mut self.overlay {
+			if let Some(key) = overlay.hotkey() {
+				if wuc.was_key_pressed(key) {
+					overlay.toggle();
+				}
+			}
+			overlay.advance(wuc.time_step());
+		}
+		if self.is_modal_active() {
+			// egui doesn't get keys from us, but the game behind the modal must not see them either
+			wuc.is_key_pressed = [false; 256];
+			wuc.is_function_key_pressed = [false; 16];
+			wuc.is_escape_pressed = false;
+			wuc.is_space_pressed = false;
+		}
+		if self.accepts_input() {
+			if routing == EguiPointerRouting::Gone {
+				self.events.push(egui::Event::PointerGone);
+				return Ok(());
+			}
+			let cursor_pos = match &self.render_target {
+				EguiRenderTarget::Screen => {
+					let layout = self.screen_layout(&wuc.window_size);
+					let pos = self.screen.pointer_pos(&layout, &wuc.mouse_pos);
+					Some(Vector2::new(pos.x, pos.y))
+				},
+				EguiRenderTarget::Canvas { size, .. } => self.pointer_uv.map(|uv| {
+					let size_in_points = *size as f32 / self.pixels_per_point;
+					Vector2::new(uv.x * size_in_points, uv.y * size_in_points)
+				}),
+			};
+			let cursor_pos = match cursor_pos {
+				Some(cursor_pos) => cursor_pos,
+				None => {
+					// the game says the pointer isn't on the surface
+					self.events.push(egui::Event::PointerGone);
+					return Ok(());
+				},
+			};
 
 			self.events.push(egui::Event::PointerMoved(egui::Pos2 {
 				x: cursor_pos.x,
 				y: cursor_pos.y,
 			}));
 
+			if routing == EguiPointerRouting::MoveOnly {
+				return Ok(());
+			}
+
 			if wuc.was_mouse_button_pressed(0) {
 				tracing::debug!("Primary Mouse Button Pressed @ {:?}", &cursor_pos);
 				wuc.consume_mouse_button_pressed(0);
@@ -184,25 +657,71 @@ impl EguiWrapperInner {
 				});
 				self.primary_mouse_button_was_pressed = false;
 			}
+
+			if self.is_modal_active() {
+				// the game behind the modal must not see any clicks
+				for button in 0..3 {
+					wuc.consume_mouse_button_pressed(button);
+				}
+			}
 		}
 		Ok(())
 	}
 
-	pub fn run<F>(&mut self, _system: &mut System, mut f: F) -> anyhow::Result<()>
+	pub fn run<F>(
+		&mut self,
+		system: &mut System,
+		viewport_size: &Vector2,
+		mut f: F,
+	) -> anyhow::Result<EguiFrame>
 	where
 		F: FnMut(&egui::Context) -> anyhow::Result<()>,
 	{
+		self.size = match &self.render_target {
+			EguiRenderTarget::Screen => *viewport_size,
+			EguiRenderTarget::Canvas { size, .. } => Vector2::new(*size as f32, *size as f32),
+		};
+		// themes only reload in development builds
+		if cfg!(debug_assertions) {
+			self.poll_theme(system);
+		}
+
 		let raw_input: egui::RawInput = self.gather_input();
 
 		self.egui_ctx.begin_frame(raw_input);
+		EguiLayerMap::add_order_markers(&self.egui_ctx);
 
-		f(&self.egui_ctx).unwrap();
+		EguiModal::reset_active(&self.egui_ctx);
+		let result = f(&self.egui_ctx);
+		self.modal_active = EguiModal::is_active(&self.egui_ctx);
+		if self.show_safe_area {
+			EguiScreen::paint_unsafe_area(&self.egui_ctx);
+		}
 
+		// the frame has to end even if `f` failed, and its textures go out with the next one
 		let full_output = self.egui_ctx.end_frame();
+		self.textures_created |= !full_output.textures_delta.set.is_empty();
+		self.textures_delta.append(full_output.textures_delta);
+		result?;
 
 		// tracing::debug!("{:?}", full_output.shapes);
-		self.shapes = full_output.shapes;
-		self.textures_delta.append(full_output.textures_delta);
+		let mut color = self.color;
+		let layers = match &self.overlay {
+			Some(overlay) if overlay.is_hidden() => Vec::new(),
+			overlay => {
+				if let Some(overlay) = overlay {
+					color.a *= overlay.visibility();
+				}
+				self.layer_map
+					.split_shapes(full_output.shapes)
+					.into_iter()
+					.map(|(layer_id, shapes)| EguiFrameLayer {
+						layer_id,
+						clipped_primitives: self.egui_ctx.tessellate(shapes),
+					})
+					.collect()
+			},
+		};
 		//tracing::debug!("{:?}", full_output.platform_output.cursor_icon);
 		/*
 				let platform_output = full_output.platform_output;
@@ -211,31 +730,50 @@ impl EguiWrapperInner {
 					my_integration.set_clipboard_text(platform_output.copied_text);
 				}
 		*/
-		Ok(())
+		Ok(EguiFrame {
+			layers,
+			textures_delta: std::mem::take(&mut self.textures_delta),
+			effect_id: self.effect_id,
+			color,
+			color_space: self.color_space,
+			render_target: self.render_target.clone(),
+			pixels_per_point: self.pixels_per_point,
+			texture_namespace: self.texture_namespace.clone(),
+			user_textures: self.user_textures.clone(),
+			screen: self.screen.clone(),
+		})
 	}
 
-	pub fn render(&mut self, system: &mut System, renderer: &mut Renderer) -> anyhow::Result<()> {
-		self.size = *renderer.viewport_size();
-		//tracing::debug!("Size {:?}", &self.size);
-		self.paint(system, renderer)?;
-		Ok(())
-	}
 	fn gather_input(&mut self) -> RawInput {
 		//tracing::debug!("pixels_per_point {}", self.pixels_per_point);
-		let screen_size_in_points = egui::Vec2 {
-			x: self.size.x / self.pixels_per_point,
-			y: self.size.y / self.pixels_per_point,
+		let (screen_rect, full_screen_rect, pixels_per_point) = match &self.render_target {
+			EguiRenderTarget::Screen => {
+				let layout = self.screen_layout(&self.size);
+				(
+					self.screen.screen_rect(&layout),
+					self.screen.full_screen_rect(&layout),
+					layout.pixels_per_point,
+				)
+			},
+			EguiRenderTarget::Canvas { .. } => {
+				let rect = egui::Rect::from_min_size(
+					Default::default(),
+					egui::vec2(
+						self.size.x / self.pixels_per_point,
+						self.size.y / self.pixels_per_point,
+					),
+				);
+				(rect, rect, self.pixels_per_point)
+			},
 		};
+		EguiScreen::store_full_screen_rect(&self.egui_ctx, full_screen_rect);
 		let ri = RawInput {
 			//dropped_files: Vec::new(),
 			//hovered_files: Vec::new(),
 			//events: 0,
 			//has_focus: 0,
-			screen_rect: Some(egui::Rect::from_center_size(
-				Default::default(),
-				screen_size_in_points,
-			)),
-			pixels_per_point: Some(self.pixels_per_point),
+			screen_rect: Some(screen_rect),
+			pixels_per_point: Some(pixels_per_point),
 			//			pixels_per_point: Some(self.pixels_per_point*2.0),
 			events: self.events.drain(..).collect(),
 			..Default::default()
@@ -243,168 +781,70 @@ impl EguiWrapperInner {
 		//tracing::debug!("{:?}", ri.events);
 		ri
 	}
+}
 
-	fn update_texture_from_image(
-		tex: &mut Texture,
-		ox: usize,
-		oy: usize,
-		image: &egui::epaint::image::ImageData,
-	) {
-		match image {
-			egui::epaint::image::ImageData::Color(color_image) => {
-				todo!();
-			},
-			egui::epaint::image::ImageData::Font(font_image) => {
-				let mut p = Vector2::zero();
-				//let mut color = 0xffffffff;
-				for y in 0..font_image.size[1] {
-					p.y = (oy + y) as f32;
-					for x in 0..font_image.size[0] {
-						p.x = (ox + x) as f32;
-						let coverage = font_image.pixels[y * font_image.size[0] + x];
-						let coverage = (coverage * 255.0) as u8;
-						let color = (coverage as u32) * 0x01010101;
-						tex.set_texel(&p, color);
-					}
-				}
-			},
-		};
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn configure_keeps_the_texture_namespace_unless_set() {
+		let mut inner = EguiWrapperInner::default();
+		inner.set_texture_namespace("debug").unwrap();
+		inner.configure(EguiWrapperConfig::default()).unwrap();
+		assert_eq!(inner.texture_namespace, "debug");
+
+		inner
+			.configure(EguiWrapperConfig::default().with_texture_namespace("hud"))
+			.unwrap();
+		assert_eq!(inner.texture_namespace, "hud");
 	}
 
-	fn paint(&mut self, system: &mut System, renderer: &mut Renderer) -> anyhow::Result<()> {
-		let shapes = std::mem::take(&mut self.shapes);
-		let mut textures_delta = std::mem::take(&mut self.textures_delta);
-
-		for (id, image_delta) in &textures_delta.set {
-			if let Some(pos) = &image_delta.pos {
-				// update existing texture
-				let name = match id {
-					egui::epaint::TextureId::Managed(mid) => {
-						format!("egui-{}", mid)
-					},
-					egui::epaint::TextureId::User(_uid) => {
-						todo!();
-					},
-				};
-
-				renderer.find_texture_mut_and_then(&name, |tex| {
-					EguiWrapperInner::update_texture_from_image(
-						tex,
-						pos[0],
-						pos[1],
-						&image_delta.image,
-					);
-					//tex.update_canvas();
-					tex.queue_canvas_update();
-				});
-			} else {
-				// create new texture
-				let size = &image_delta.image.size();
-				let size = if size[0] > size[1] { size[0] } else { size[1] };
-				let name = match id {
-					egui::epaint::TextureId::Managed(mid) => {
-						format!("egui-{}", mid)
-					},
-					egui::epaint::TextureId::User(_uid) => {
-						todo!();
-					},
-				};
-				let mut tex = Texture::create_canvas(&name, size as u32);
-				let sy = image_delta.image.size()[1] as f32 / image_delta.image.size()[0] as f32;
-				let mtx = Matrix32::identity().with_scaling_xy(1.0, sy);
-				tex.set_mtx(&mtx);
-
-				EguiWrapperInner::update_texture_from_image(&mut tex, 0, 0, &image_delta.image);
-
-				tex.update_canvas();
-				let tid = renderer.register_texture(tex);
-				self.texture_ids.insert(*id, tid);
-			}
-		}
-
-		let clipped_primitives = self.egui_ctx.tessellate(shapes);
-		//tracing::debug!("{:?}", &clipped_primitives);
-
-		renderer.use_layer(self.layer_id);
-		renderer.use_effect(self.effect_id);
-
-		for egui::ClippedPrimitive {
-			clip_rect: _,
-			primitive,
-		} in clipped_primitives
-		{
-			//tracing::debug!("ClipRect: {:?}", clip_rect);
-			match primitive {
-				egui::epaint::Primitive::Mesh(mesh) => {
-					//tracing::debug!("Mesh: {:?}", &mesh );
-					self.paint_mesh(renderer, mesh)?;
-				},
-				p => {
-					tracing::warn!("Unsupported primitive {:?}", &p);
-				},
-			};
-		}
-
-		Ok(())
+	#[test]
+	fn user_textures_keep_their_id() {
+		let mut inner = EguiWrapperInner::default();
+		let cursor = inner.register_user_texture("cursor");
+		let logo = inner.register_user_texture("logo");
+		assert_eq!(cursor, egui::TextureId::User(0));
+		assert_eq!(logo, egui::TextureId::User(1));
+		assert_eq!(inner.register_user_texture("cursor"), cursor);
 	}
 
-	fn paint_mesh(&self, renderer: &mut Renderer, mesh: egui::epaint::Mesh) -> anyhow::Result<()> {
-		let mut vertice_map = HashMap::new();
-
-		//let size = renderer.size();
-		//let aspect_ratio = renderer.aspect_ratio();
-
-		//tracing::debug!("Size: {:?}", &size );
-		//tracing::debug!("Aspect Ratio: {:?}", &aspect_ratio );
-
-		let texture_id = &mesh.texture_id;
-		let tid = match self.texture_ids.get(texture_id) {
-			Some(tid) => tid,
-			None => return Ok(()),
-		};
-		// tracing::debug!("Using texture {}", tid);
-		renderer.use_texture_id_in_channel(*tid, 0);
-		//		renderer.render_textured_fullscreen_quad();
-
-		for (i, v) in mesh.vertices.iter().enumerate() {
-			/*
-			gl_Position = vec4(
-			2.0 * a_pos.x / u_screen_size.x - 1.0,
-			1.0 - 2.0 * a_pos.y / u_screen_size.y,
-			0.0,
-			1.0);
-			*/
-			let vertex = Vector2::new( v.pos.x, v.pos.y )
-			.scaled_vector2( &Vector2::new( 1.0, -1.0 ) ) // upside down :(
-			//.scaled( 4.0 )
-			//.add( &Vector2::new( 0.0, 0.0 ) )
-			//.add( &Vector2::new( 5.0*500.0, 4.0*-500.0 ) )
-			;
-
-			//tracing::debug!("TC {}, {}", v.uv.x, v.uv.y);
-			renderer.set_tex_coords(&Vector2::new(v.uv.x, v.uv.y /*/8.0*/));
-			let color = oml_game::renderer::Color::from_rgba(
-				v.color.r() as f32 / 255.0,
-				v.color.g() as f32 / 255.0,
-				v.color.b() as f32 / 255.0,
-				v.color.a() as f32 / 255.0,
-			);
-
-			let color = color * self.color;
-
-			renderer.set_color(&color);
-			let vi = renderer.add_vertex(&vertex);
-			vertice_map.insert(i, vi);
-			//tracing::debug!("{} -> {}, {:?}", i, vi, v.pos );
-		}
-
-		for t in mesh.indices.chunks(3) {
-			let tm: Vec<u32> = t
-				.iter()
-				.map(|i| *vertice_map.get(&(*i as usize)).unwrap())
-				.collect();
-			renderer.add_triangle(tm[0], tm[1], tm[2]);
-		}
-		Ok(())
+	#[test]
+	fn texture_namespace_is_fixed_once_textures_exist() {
+		let mut inner = EguiWrapperInner::default();
+		inner.set_texture_namespace("debug").unwrap();
+		inner.textures_created = true;
+
+		assert!(inner.set_texture_namespace("debug").is_ok());
+		assert!(inner.set_texture_namespace("hud").is_err());
+		assert!(inner
+			.configure(EguiWrapperConfig::default().with_texture_namespace("hud"))
+			.is_err());
+		assert_eq!(inner.texture_namespace, "debug");
+	}
+	#[test]
+	fn failed_runs_keep_their_textures() {
+		let mut system = System::new();
+		let mut inner = EguiWrapperInner::default();
+		inner.setup(1.0).unwrap();
+		let viewport_size = Vector2::new(320.0, 200.0);
+
+		let result = inner.run(&mut system, &viewport_size, |ctx| {
+			egui::Window::new("broken").show(ctx, |ui| ui.label("label"));
+			anyhow::bail!("broken ui")
+		});
+		assert!(result.is_err());
+		assert!(inner.textures_created);
+
+		// the font texture only comes with the first frame
+		let frame = inner
+			.run(&mut system, &viewport_size, |_ctx| Ok(()))
+			.unwrap();
+		assert!(frame
+			.textures_delta
+			.set
+			.iter()
+			.any(|(id, _)| *id == egui::TextureId::default()));
 	}
 }
@@ -0,0 +1,223 @@
+use oml_game::renderer::Color;
+use oml_game::renderer::Renderer;
+
+use crate::EguiColorSpace;
+use crate::EguiEffect;
+use crate::EguiLayerMap;
+use crate::EguiOverlay;
+use crate::EguiScreen;
+
+/// All the settings needed to get an `EguiWrapper` going, in one place.
+///
+/// ```ignore
+/// let config = EguiWrapperConfig::default()
+///     .with_pixels_per_point(window.scale_factor() as f32)
+///     .with_layer_id(LayerId::Egui as u8)
+///     .with_egui_effect(EffectId::Egui as u16);
+/// egui_wrapper.configure(config, &mut renderer)?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct EguiWrapperConfig {
+	pixels_per_point:  f32,
+	layer_map:         EguiLayerMap,
+	effect:            Option<(u16, String)>,
+	color:             Color,
+	color_space:       EguiColorSpace,
+	input_disabled:    bool,
+	visuals:           Option<egui::Visuals>,
+	fonts:             Option<egui::FontDefinitions>,
+	texture_namespace: String,
+	screen:            EguiScreen,
+	overlay:           Option<EguiOverlay>,
+}
+
+impl Default for EguiWrapperConfig {
+	fn default() -> Self {
+		Self {
+			pixels_per_point:  1.0,
+			layer_map:         EguiLayerMap::new(0),
+			effect:            None,
+			color:             Color::from_rgba(1.0, 1.0, 1.0, 1.0),
+			color_space:       EguiColorSpace::Gamma,
+			input_disabled:    false,
+			visuals:           None,
+			fonts:             None,
+			texture_namespace: String::new(),
+			screen:            EguiScreen::default(),
+			overlay:           None,
+		}
+	}
+}
+
+impl EguiWrapperConfig {
+	pub fn with_pixels_per_point(mut self, pixels_per_point: f32) -> Self {
+		self.pixels_per_point = pixels_per_point;
+		self
+	}
+
+	/// Draws all egui output into `layer_id`.
+	pub fn with_layer_id(mut self, layer_id: u8) -> Self {
+		self.layer_map = EguiLayerMap::new(layer_id);
+		self
+	}
+
+	pub fn with_layer_map(mut self, layer_map: EguiLayerMap) -> Self {
+		self.layer_map = layer_map;
+		self
+	}
+
+	/// The effect has to be registered with the `Renderer` under `name` before calling `EguiWrapper::configure`.
+	pub fn with_effect(mut self, effect_id: u16, name: &str) -> Self {
+		self.effect = Some((effect_id, name.to_string()));
+		self
+	}
+
+	/// Uses the built-in effect for the configured color space, see `EguiEffect::register_for_color_space`.
+	pub fn with_egui_effect(self, effect_id: u16) -> Self {
+		let name = EguiEffect::name(self.color_space);
+		self.with_effect(effect_id, name)
+	}
+
+	/// Tint applied to everything egui draws, with straight alpha.
+	pub fn with_color(mut self, color: &Color) -> Self {
+		self.color = *color;
+		self
+	}
+
+	/// Also switches a built-in effect to the variant for `color_space`.
+	pub fn with_color_space(mut self, color_space: EguiColorSpace) -> Self {
+		self.color_space = color_space;
+		if let Some((_, name)) = &mut self.effect {
+			if EguiEffect::color_space_of(name).is_some() {
+				*name = EguiEffect::name(color_space).to_string();
+			}
+		}
+		self
+	}
+
+	pub fn with_input_disabled(mut self, input_disabled: bool) -> Self {
+		self.input_disabled = input_disabled;
+		self
+	}
+
+	pub fn with_visuals(mut self, visuals: egui::Visuals) -> Self {
+		self.visuals = Some(visuals);
+		self
+	}
+
+	pub fn with_fonts(mut self, fonts: egui::FontDefinitions) -> Self {
+		self.fonts = Some(fonts);
+		self
+	}
+
+	/// Prefixes the renderer texture names, to tell the textures of several `EguiWrapper`s apart.
+	///
+	/// Left empty, `EguiWrapper::configure` keeps the current namespace.
+	pub fn with_texture_namespace(mut self, texture_namespace: &str) -> Self {
+		self.texture_namespace = texture_namespace.to_string();
+		self
+	}
+
+	pub fn with_screen(mut self, screen: EguiScreen) -> Self {
+		self.screen = screen;
+		self
+	}
+
+	/// Shows and hides the wrapper with a fade, see `EguiOverlay`.
+	pub fn with_overlay(mut self, overlay: EguiOverlay) -> Self {
+		self.overlay = Some(overlay);
+		self
+	}
+
+	pub fn pixels_per_point(&self) -> f32 {
+		self.pixels_per_point
+	}
+
+	pub fn layer_map(&self) -> &EguiLayerMap {
+		&self.layer_map
+	}
+
+	pub fn effect_id(&self) -> Option<u16> {
+		self.effect.as_ref().map(|(id, _)| *id)
+	}
+
+	pub fn color(&self) -> &Color {
+		&self.color
+	}
+
+	pub fn color_space(&self) -> EguiColorSpace {
+		self.color_space
+	}
+
+	pub fn input_disabled(&self) -> bool {
+		self.input_disabled
+	}
+
+	pub fn visuals(&self) -> Option<&egui::Visuals> {
+		self.visuals.as_ref()
+	}
+
+	pub fn fonts(&self) -> Option<&egui::FontDefinitions> {
+		self.fonts.as_ref()
+	}
+
+	pub fn texture_namespace(&self) -> &str {
+		&self.texture_namespace
+	}
+
+	pub fn screen(&self) -> &EguiScreen {
+		&self.screen
+	}
+
+	pub fn overlay(&self) -> Option<&EguiOverlay> {
+		self.overlay.as_ref()
+	}
+
+	pub fn validate(&self, renderer: &mut Renderer) -> anyhow::Result<()> {
+		// also catches NaN
+		if self.pixels_per_point.is_nan() || self.pixels_per_point <= 0.0 {
+			anyhow::bail!(
+				"pixels_per_point must be greater than 0, got {}",
+				self.pixels_per_point
+			);
+		}
+
+		let (effect_id, name) = match &self.effect {
+			Some(effect) => effect,
+			None => anyhow::bail!("No effect configured, use with_effect"),
+		};
+
+		if let Some(color_space) = EguiEffect::color_space_of(name) {
+			if color_space != self.color_space {
+				anyhow::bail!(
+					"Effect {} renders {:?}, but the color space is configured as {:?}",
+					name,
+					color_space,
+					self.color_space
+				);
+			}
+		}
+
+		let mut registered_id = None;
+		let found = renderer.find_effect_mut_and_then(name, |effect| {
+			registered_id = Some(effect.id());
+		});
+		if !found {
+			anyhow::bail!(
+				"Effect {} ({}) is not registered with the renderer",
+				name,
+				effect_id
+			);
+		}
+		if registered_id != Some(*effect_id) {
+			anyhow::bail!(
+				"Effect {} is registered as {:?}, but configured as {}",
+				name,
+				registered_id,
+				effect_id
+			);
+		}
+
+		Ok(())
+	}
+}
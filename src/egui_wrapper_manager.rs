@@ -0,0 +1,122 @@
+use oml_game::renderer::Renderer;
+use oml_game::system::System;
+use oml_game::window::WindowUpdateContext;
+
+use crate::egui_wrapper::EguiPointerRouting;
+use crate::EguiWrapper;
+
+/// Runs several independent `EguiWrapper`s, e.g. a debug overlay on top of a game HUD.
+///
+/// Wrappers are stacked in the order they are added, the last one is on top.
+/// The pointer goes to the topmost wrapper that wants it, the ones above only see it hovering,
+/// and the ones below don't see it at all.
+#[derive(Debug, Default)]
+pub struct EguiWrapperManager {
+	wrappers: Vec<(String, EguiWrapper)>,
+}
+
+impl EguiWrapperManager {
+	/// Adds `wrapper` on top, using `name` as its texture namespace.
+	///
+	/// Fails if `name` is already used, or if `wrapper` already ran with a different namespace.
+	pub fn add(&mut self, name: &str, mut wrapper: EguiWrapper) -> anyhow::Result<()> {
+		if self.get(name).is_some() {
+			anyhow::bail!("There already is an egui wrapper named {}", name);
+		}
+		wrapper.set_texture_namespace(name)?;
+		self.wrappers.push((name.to_string(), wrapper));
+		Ok(())
+	}
+
+	pub fn remove(&mut self, name: &str) -> Option<EguiWrapper> {
+		let pos = self.wrappers.iter().position(|(n, _)| n == name)?;
+		Some(self.wrappers.remove(pos).1)
+	}
+
+	pub fn get(&self, name: &str) -> Option<&EguiWrapper> {
+		self.wrappers
+			.iter()
+			.find(|(n, _)| n == name)
+			.map(|(_, w)| w)
+	}
+
+	pub fn get_mut(&mut self, name: &str) -> Option<&mut EguiWrapper> {
+		self.wrappers
+			.iter_mut()
+			.find(|(n, _)| n == name)
+			.map(|(_, w)| w)
+	}
+
+	pub fn move_to_top(&mut self, name: &str) {
+		if let Some(pos) = self.wrappers.iter().position(|(n, _)| n == name) {
+			let wrapper = self.wrappers.remove(pos);
+			self.wrappers.push(wrapper);
+		}
+	}
+
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.wrappers.iter().map(|(n, _)| n.as_str())
+	}
+
+	/// The wrapper that gets clicks this frame, based on what the wrappers wanted last frame.
+	pub fn pointer_owner(&self) -> Option<&str> {
+		self.wrappers
+			.iter()
+			.rev()
+			.find(|(_, w)| w.wants_pointer_input())
+			.map(|(n, _)| n.as_str())
+	}
+
+	pub fn update(&mut self, wuc: &mut WindowUpdateContext) -> anyhow::Result<()> {
+		let owner = self
+			.wrappers
+			.iter()
+			.rposition(|(_, w)| w.wants_pointer_input());
+
+		for (i, (_, wrapper)) in self.wrappers.iter_mut().enumerate() {
+			let routing = match owner {
+				Some(owner) if i == owner => EguiPointerRouting::Full,
+				Some(owner) if i < owner => EguiPointerRouting::Gone,
+				_ => EguiPointerRouting::MoveOnly,
+			};
+			wrapper.update_routed(wuc, routing)?;
+		}
+		Ok(())
+	}
+
+	pub fn run<F>(&self, name: &str, system: &mut System, f: F) -> anyhow::Result<()>
+	where
+		F: FnMut(&egui::Context) -> anyhow::Result<()>,
+	{
+		match self.get(name) {
+			Some(wrapper) => wrapper.run(system, f),
+			None => anyhow::bail!("No egui wrapper named {}", name),
+		}
+	}
+
+	/// Renders all wrappers, bottom to top.
+	pub fn render(&self, system: &mut System, renderer: &mut Renderer) -> anyhow::Result<()> {
+		for (_, wrapper) in self.wrappers.iter() {
+			wrapper.render(system, renderer)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn names_are_only_used_once() {
+		let mut manager = EguiWrapperManager::default();
+		manager.add("hud", EguiWrapper::default()).unwrap();
+		manager.add("debug", EguiWrapper::default()).unwrap();
+		assert!(manager.add("hud", EguiWrapper::default()).is_err());
+		assert_eq!(manager.names().collect::<Vec<_>>(), vec!["hud", "debug"]);
+
+		assert!(manager.remove("hud").is_some());
+		manager.add("hud", EguiWrapper::default()).unwrap();
+		assert_eq!(manager.names().collect::<Vec<_>>(), vec!["debug", "hud"]);
+	}
+}
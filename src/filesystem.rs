@@ -0,0 +1,205 @@
+use oml_game::system::filesystem::Filesystem;
+use oml_game::system::System;
+
+/// Reads a whole file from the default filesystem of `system`.
+///
+/// Streams only hand out single bytes, so this fills a buffer of the whole size, which disk streams back with a `BufReader`.
+pub(crate) fn read_bytes(system: &mut System, name: &str) -> anyhow::Result<Vec<u8>> {
+	read_all(system.default_filesystem_mut().as_mut(), name)
+}
+
+fn read_all(fs: &mut dyn Filesystem, name: &str) -> anyhow::Result<Vec<u8>> {
+	let mut f = fs.open(name);
+	if !f.is_valid() {
+		anyhow::bail!("Couldn't open {}", name);
+	}
+
+	let mut bytes = vec![0; f.size()];
+	for b in bytes.iter_mut() {
+		*b = f.read_u8();
+	}
+	if f.pos() != bytes.len() {
+		anyhow::bail!(
+			"Only read {} of {} bytes from {}",
+			f.pos(),
+			bytes.len(),
+			name
+		);
+	}
+	Ok(bytes)
+}
+
+/// The size of a file, without reading it.
+pub(crate) fn size(system: &mut System, name: &str) -> Option<usize> {
+	let f = system.default_filesystem_mut().open(name);
+	f.is_valid().then(|| f.size())
+}
+
+pub(crate) fn read_string(system: &mut System, name: &str) -> anyhow::Result<String> {
+	let bytes = read_bytes(system, name)?;
+	Ok(String::from_utf8(bytes)?)
+}
+
+/// Writes a whole file via the default filesystem of `system`, which needs a writable layer.
+///
+/// Streams only take single bytes and drop the ones they fail to write,
+/// so the position after writing tells whether everything arrived.
+pub(crate) fn write_string(system: &mut System, name: &str, data: &str) -> anyhow::Result<()> {
+	write_bytes(
+		system.default_filesystem_mut().as_mut(),
+		name,
+		data.as_bytes(),
+	)
+}
+
+fn write_bytes(fs: &mut dyn Filesystem, name: &str, data: &[u8]) -> anyhow::Result<()> {
+	let mut f = fs.create(name, true);
+	if !f.is_valid() {
+		anyhow::bail!("Couldn't create {}", name);
+	}
+
+	for b in data {
+		f.write_u8(*b);
+	}
+	if f.pos() != data.len() {
+		anyhow::bail!("Only wrote {} of {} bytes to {}", f.pos(), data.len(), name);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use oml_game::system::filesystem_disk::FilesystemDisk;
+	use oml_game::system::filesystem_stream::FilesystemStream;
+	use oml_game::system::filesystem_stream::FilesystemStreamMode;
+	use oml_game::system::filesystem_stream_memory::FilesystemStreamMemory;
+
+	use super::*;
+
+	struct MemoryFilesystem {
+		data: Vec<u8>,
+	}
+
+	impl Filesystem for MemoryFilesystem {
+		fn open(&mut self, name: &str) -> Box<dyn FilesystemStream> {
+			Box::new(FilesystemStreamMemory::open(name, &self.data))
+		}
+
+		fn name(&self) -> &str {
+			"test"
+		}
+
+		fn filesystem_type(&self) -> &str {
+			"Memory"
+		}
+	}
+
+	/// A disk that fills up after `capacity` bytes.
+	struct FullFilesystem {
+		capacity: usize,
+	}
+
+	struct FullStream {
+		capacity: usize,
+		pos:      usize,
+	}
+
+	impl Filesystem for FullFilesystem {
+		fn open(&mut self, name: &str) -> Box<dyn FilesystemStream> {
+			self.create(name, false)
+		}
+
+		fn create(&mut self, _name: &str, _overwrite: bool) -> Box<dyn FilesystemStream> {
+			Box::new(FullStream {
+				capacity: self.capacity,
+				pos:      0,
+			})
+		}
+
+		fn name(&self) -> &str {
+			"full"
+		}
+
+		fn filesystem_type(&self) -> &str {
+			"Full"
+		}
+	}
+
+	impl FilesystemStream for FullStream {
+		fn size(&self) -> usize {
+			0
+		}
+		fn pos(&self) -> usize {
+			self.pos
+		}
+		fn set_pos(&mut self, _pos: usize) {}
+		fn read_u8(&mut self) -> u8 {
+			0
+		}
+		fn write_u8(&mut self, _data: u8) {
+			if self.pos < self.capacity {
+				self.pos += 1;
+			}
+		}
+		fn is_valid(&self) -> bool {
+			true
+		}
+		fn eof(&self) -> bool {
+			true
+		}
+		fn name(&self) -> &str {
+			"full"
+		}
+		fn filesystem_stream_type(&self) -> &str {
+			"Full"
+		}
+		fn mode(&self) -> FilesystemStreamMode {
+			FilesystemStreamMode::Write
+		}
+	}
+
+	#[test]
+	fn writes_to_disk() {
+		let dir = std::env::temp_dir().join(format!("oml-game-egui-test-{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let mut fs = FilesystemDisk::new(dir.to_str().unwrap());
+		fs.enable_write();
+
+		write_bytes(&mut fs, "memory.ron", b"(windows: [])").unwrap();
+		assert_eq!(
+			std::fs::read(dir.join("memory.ron")).unwrap(),
+			b"(windows: [])"
+		);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn reads_whole_files() {
+		let mut fs = MemoryFilesystem {
+			data: b"font data".to_vec(),
+		};
+		assert_eq!(read_all(&mut fs, "font.ttf").unwrap(), b"font data");
+
+		let mut fs = MemoryFilesystem { data: Vec::new() };
+		assert!(read_all(&mut fs, "empty.ttf").unwrap().is_empty());
+
+		let mut fs = FilesystemDisk::new("/nonexistent/oml-game-egui");
+		assert!(read_all(&mut fs, "font.ttf").is_err());
+	}
+
+	#[test]
+	fn reports_files_that_can_not_be_created() {
+		let mut fs = FilesystemDisk::new("/nonexistent/oml-game-egui");
+		fs.enable_write();
+		assert!(write_bytes(&mut fs, "memory.ron", b"()").is_err());
+	}
+
+	#[test]
+	fn reports_short_writes() {
+		let mut fs = FullFilesystem { capacity: 3 };
+		assert!(write_bytes(&mut fs, "memory.ron", b"abc").is_ok());
+		let e = write_bytes(&mut fs, "memory.ron", b"abcdef").unwrap_err();
+		assert_eq!(e.to_string(), "Only wrote 3 of 6 bytes to memory.ron");
+	}
+}
@@ -1,5 +1,75 @@
 mod egui_wrapper;
 pub use egui_wrapper::EguiWrapper;
 
+mod egui_wrapper_config;
+pub use egui_wrapper_config::EguiWrapperConfig;
+
+mod egui_wrapper_manager;
+pub use egui_wrapper_manager::EguiWrapperManager;
+
+mod egui_color;
+pub use egui_color::EguiColorSpace;
+
+mod egui_effect;
+pub use egui_effect::EguiEffect;
+
+mod egui_fonts;
+pub use egui_fonts::EguiFont;
+pub use egui_fonts::EguiFontPriority;
+
+mod egui_frame;
+pub use egui_frame::EguiFrame;
+pub use egui_frame::EguiFrameLayer;
+
+mod egui_layer_map;
+pub use egui_layer_map::EguiLayerMap;
+
+mod egui_memory;
+
+mod egui_modal;
+pub use egui_modal::EguiModal;
+
+mod egui_overlay;
+pub use egui_overlay::EguiOverlay;
+pub use egui_overlay::EguiOverlayState;
+
+mod egui_painter;
+
+mod egui_rasterizer;
+
+mod egui_render_target;
+pub use egui_render_target::EguiRenderTarget;
+
+mod egui_screen;
+pub use egui_screen::EguiOrigin;
+pub use egui_screen::EguiSafeArea;
+pub use egui_screen::EguiScalePolicy;
+pub use egui_screen::EguiScreen;
+pub use egui_screen::EguiScreenLayout;
+
+mod egui_theme;
+pub use egui_theme::EguiTheme;
+
+mod filesystem;
+
+mod egui_telemetry_budget;
+
+mod egui_telemetry_config;
+
+mod egui_telemetry_distribution;
+pub use egui_telemetry_distribution::EguiTelemetryView;
+
+mod egui_telemetry_samples;
+pub use egui_telemetry_samples::EguiTraceKind;
+
+mod egui_telemetry_snapshot;
+
+mod egui_telemetry_stats;
+pub use egui_telemetry_stats::EguiStatsScope;
+
+mod egui_telemetry_trigger;
+pub use egui_telemetry_trigger::EguiTrigger;
+pub use egui_telemetry_trigger::EguiTriggerCondition;
+
 mod egui_telemetry_widget;
 pub use egui_telemetry_widget::EguiTelemetryWidget;
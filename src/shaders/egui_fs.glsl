@@ -2,13 +2,11 @@
 out vec4 FragColor;
 in vec2 oTexCoords;
 in vec4 oColor;
-// varying ?
 
 uniform sampler2D texture0;
 
 void main()
 {
-//	FragColor = oColor * texture( texture0, oTexCoords ) + vec4( oTexCoords.y, 0, 0, 1);
+	// both vertex color and texture are premultiplied sRGB, blend with One, OneMinusSrcAlpha
 	FragColor = oColor * texture( texture0, oTexCoords );
-	//FragColor = oColor * vec4( oTexCoords, 1, 2);
-} 
+}
@@ -0,0 +1,21 @@
+#version 330 core
+out vec4 FragColor;
+in vec2 oTexCoords;
+in vec4 oColor;
+
+uniform sampler2D texture0;
+
+vec3 linear_from_gamma(vec3 c)
+{
+	bvec3 cutoff = lessThan(c, vec3(0.04045));
+	vec3 lower = c / vec3(12.92);
+	vec3 higher = pow((c + vec3(0.055)) / vec3(1.055), vec3(2.4));
+	return mix(higher, lower, vec3(cutoff));
+}
+
+void main()
+{
+	// vertex color is already linear, the texture is still stored as premultiplied sRGB
+	vec4 texel = texture( texture0, oTexCoords );
+	FragColor = oColor * vec4( linear_from_gamma( texel.rgb ), texel.a );
+}
@@ -10,6 +10,7 @@ uniform mat4 modelViewProjectionMatrix;
 void main()
 {
 	oTexCoords = texCoords;
+	// premultiplied, already converted to the framebuffer color space on the CPU
 	oColor = color;
-    gl_Position = modelViewProjectionMatrix * vec4(aPos.x, aPos.y, aPos.z, 1.0);
+	gl_Position = modelViewProjectionMatrix * vec4(aPos.x, aPos.y, aPos.z, 1.0);
 }